@@ -16,266 +16,6170 @@
 //!     .unwrap();
 //! ```
 
-use std::{io, process};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    error,
+    ffi::{OsStr, OsString},
+    fmt, fs, io,
+    iter::FromIterator,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
+
+#[cfg(windows)]
+use winreg::{
+    enums::{HKEY_CLASSES_ROOT, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE},
+    RegKey, HKEY,
+};
 
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(windows)]
+const OUTLOOK_SUBKEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\OUTLOOK.EXE";
+
 lazy_static! {
-    static ref OUTLOOK_EXE: Option<&'static str> = {
-        use winreg::{enums::HKEY_LOCAL_MACHINE, RegKey};
+    static ref OUTLOOK_EXE: Option<String> = locate_outlook_exe();
+}
 
-        const OUTLOOK_SUBKEY: &str =
-            "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\OUTLOOK.EXE";
+#[cfg(windows)]
+fn locate_outlook_exe() -> Option<String> {
+    resolve_outlook_exe(app_path)
+}
 
-        let subkey = match RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(OUTLOOK_SUBKEY) {
-            Ok(subkey) => subkey,
-            Err(_) => return None,
-        };
-        let value: String = match subkey.get_value("") {
-            Ok(value) => value,
-            Err(_) => return None,
-        };
-        Some(Box::leak(value.into_boxed_str()))
-    };
+/// Outlook is only installed on Windows, so it is never located elsewhere.
+#[cfg(not(windows))]
+fn locate_outlook_exe() -> Option<String> {
+    None
 }
 
-fn percent_escape(s: &str) -> String {
-    s.replace('%', "%25") // has to be first to avoid double-encoding '%'
-        .replace('"', "%22")
-        .replace('&', "%26")
-        .replace('?', "%3F")
+/// Reads the OUTLOOK.EXE App Paths entry from a registry hive.
+#[cfg(windows)]
+fn app_path(hkey: HKEY) -> Option<String> {
+    let subkey = RegKey::predef(hkey).open_subkey(OUTLOOK_SUBKEY).ok()?;
+    subkey.get_value("").ok()
 }
 
-/// The `MessageBuilder` type, for drafting Outlook email messages.
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct MessageBuilder {
-    subj: String,
-    to: Vec<String>,
-    cc: Vec<String>,
-    bcc: Vec<String>,
-    body: String,
-    file: String,
+/// Resolves the OUTLOOK.EXE path the way Windows resolves App Paths,
+/// trying `HKEY_LOCAL_MACHINE` first and then `HKEY_CURRENT_USER`.
+#[cfg(windows)]
+fn resolve_outlook_exe<F>(lookup: F) -> Option<String>
+where
+    F: Fn(HKEY) -> Option<String>,
+{
+    lookup(HKEY_LOCAL_MACHINE).or_else(|| lookup(HKEY_CURRENT_USER))
 }
 
-impl MessageBuilder {
-    /// Creates a new `MessageBuilder`.
-    #[inline]
-    #[must_use]
-    pub const fn new() -> Self {
-        Self {
-            subj: String::new(),
-            to: Vec::new(),
-            cc: Vec::new(),
-            bcc: Vec::new(),
-            body: String::new(),
-            file: String::new(),
-        }
+/// Returns the path to OUTLOOK.EXE found in the registry, if any.
+///
+/// The registry is only read once, the first time the path is needed.
+/// On platforms other than Windows, this always returns `None`.
+#[inline]
+#[must_use]
+pub fn outlook_path() -> Option<&'static str> {
+    OUTLOOK_EXE.as_deref()
+}
+
+/// Returns whether OUTLOOK.EXE could be located in the registry.
+///
+/// This only checks that Outlook is installed, not that it can run.
+/// It is cheap to call repeatedly, since the registry is only read once.
+#[inline]
+#[must_use]
+pub fn is_available() -> bool {
+    outlook_path().is_some()
+}
+
+/// Registry key under `HKEY_LOCAL_MACHINE` describing a Click-to-Run
+/// installation of Office, such as Microsoft 365.
+#[cfg(windows)]
+const CLICK_TO_RUN_SUBKEY: &str = "SOFTWARE\\Microsoft\\Office\\ClickToRun\\Configuration";
+
+/// Registry key under `HKEY_CLASSES_ROOT` naming the registered version
+/// of Outlook's automation server, e.g. `Outlook.Application.16`.
+#[cfg(windows)]
+const CUR_VER_SUBKEY: &str = "Outlook.Application\\CurVer";
+
+/// Extracts the major version from a `CurVer` value like
+/// `Outlook.Application.16`.
+#[cfg(any(windows, test))]
+fn cur_ver_version(cur_ver: &str) -> Option<String> {
+    let version = cur_ver.strip_prefix("Outlook.Application.")?;
+    if !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) {
+        Some(version.to_owned())
+    } else {
+        None
     }
+}
 
-    /// Adds a subject to the email.
-    ///
-    /// This should only be called once per `MessageBuilder` instance.
-    #[inline]
-    #[must_use]
-    pub fn with_subject<S>(self, subj: S) -> Self
-    where
-        S: Into<String>,
-    {
-        debug_assert!(self.subj.is_empty(), "Outlook subject already provided");
-        Self {
-            subj: subj.into(),
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: self.body,
-            file: self.file,
+/// Returns the version of the installed Outlook, if it can be determined.
+///
+/// For a Click-to-Run installation, such as Microsoft 365, this is the
+/// full version, e.g. `16.0.17328.20162`, read from the `VersionToReport`
+/// value of `HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Office\ClickToRun\Configuration`.
+/// Otherwise, it is only the major version, e.g. `15` for Outlook 2013,
+/// read from `HKEY_CLASSES_ROOT\Outlook.Application\CurVer`. Outlook 2016
+/// and later all report a major version of `16`.
+///
+/// On platforms other than Windows, this always returns `None`.
+#[cfg(windows)]
+#[must_use]
+pub fn outlook_version() -> Option<String> {
+    RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey(CLICK_TO_RUN_SUBKEY)
+        .and_then(|key| key.get_value("VersionToReport"))
+        .ok()
+        .or_else(|| {
+            let key = RegKey::predef(HKEY_CLASSES_ROOT)
+                .open_subkey(CUR_VER_SUBKEY)
+                .ok()?;
+            let cur_ver: String = key.get_value("").ok()?;
+            cur_ver_version(&cur_ver)
+        })
+}
+
+/// Returns the version of the installed Outlook, if it can be determined.
+///
+/// For a Click-to-Run installation, such as Microsoft 365, this is the
+/// full version, e.g. `16.0.17328.20162`, read from the `VersionToReport`
+/// value of `HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Office\ClickToRun\Configuration`.
+/// Otherwise, it is only the major version, e.g. `15` for Outlook 2013,
+/// read from `HKEY_CLASSES_ROOT\Outlook.Application\CurVer`. Outlook 2016
+/// and later all report a major version of `16`.
+///
+/// On platforms other than Windows, this always returns `None`.
+#[cfg(not(windows))]
+#[must_use]
+pub fn outlook_version() -> Option<String> {
+    None
+}
+
+/// Registry keys under `HKEY_CURRENT_USER` for Outlook 2016 and later,
+/// Outlook 2013, and earlier versions, in the order they are consulted.
+///
+/// Each entry is the key holding the `DefaultProfile` value, and the
+/// key whose subkeys are the profiles.
+#[cfg(windows)]
+const PROFILE_SUBKEYS: [(&str, &str); 3] = [
+    (
+        "Software\\Microsoft\\Office\\16.0\\Outlook",
+        "Software\\Microsoft\\Office\\16.0\\Outlook\\Profiles",
+    ),
+    (
+        "Software\\Microsoft\\Office\\15.0\\Outlook",
+        "Software\\Microsoft\\Office\\15.0\\Outlook\\Profiles",
+    ),
+    (
+        "Software\\Microsoft\\Windows NT\\CurrentVersion\\Windows Messaging Subsystem\\Profiles",
+        "Software\\Microsoft\\Windows NT\\CurrentVersion\\Windows Messaging Subsystem\\Profiles",
+    ),
+];
+
+/// Returns the name of the default Outlook profile, if any.
+///
+/// This reads the `DefaultProfile` value from the registry, trying
+/// Outlook 2016 and later, then Outlook 2013, then earlier versions.
+/// On platforms other than Windows, this always returns `None`.
+#[must_use]
+#[cfg(windows)]
+pub fn default_profile() -> Option<String> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    PROFILE_SUBKEYS.iter().find_map(|(subkey, _)| {
+        let key = hkcu.open_subkey(subkey).ok()?;
+        key.get_value("DefaultProfile").ok()
+    })
+}
+
+/// Returns the name of the default Outlook profile, if any.
+///
+/// This reads the `DefaultProfile` value from the registry, trying
+/// Outlook 2016 and later, then Outlook 2013, then earlier versions.
+/// On platforms other than Windows, this always returns `None`.
+#[must_use]
+#[cfg(not(windows))]
+pub fn default_profile() -> Option<String> {
+    None
+}
+
+/// Returns the names of the Outlook profiles, for use with
+/// `MessageBuilder::with_profile()`.
+///
+/// This enumerates the profiles in the registry of the first version
+/// found, trying Outlook 2016 and later (which includes Microsoft 365),
+/// then Outlook 2013, then earlier versions. If no profiles are found,
+/// including on platforms other than Windows, an empty `Vec` is returned.
+#[must_use]
+#[cfg(windows)]
+pub fn list_profiles() -> Vec<String> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    PROFILE_SUBKEYS
+        .iter()
+        .find_map(|(_, subkey)| hkcu.open_subkey(subkey).ok())
+        .map(|key| key.enum_keys().filter_map(Result::ok).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the names of the Outlook profiles, for use with
+/// `MessageBuilder::with_profile()`.
+///
+/// This enumerates the profiles in the registry of the first version
+/// found, trying Outlook 2016 and later (which includes Microsoft 365),
+/// then Outlook 2013, then earlier versions. If no profiles are found,
+/// including on platforms other than Windows, an empty `Vec` is returned.
+#[must_use]
+#[cfg(not(windows))]
+pub fn list_profiles() -> Vec<String> {
+    Vec::new()
+}
+
+/// Registry key under `HKEY_CURRENT_USER` holding the Office sign-in
+/// identity of Outlook 2016 and later.
+#[cfg(windows)]
+const IDENTITY_SUBKEY: &str = "Software\\Microsoft\\Office\\16.0\\Common\\Identity";
+
+/// Reads the address the user signed in to Office with, if any.
+#[cfg(windows)]
+fn identity_address() -> Option<String> {
+    let key = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(IDENTITY_SUBKEY)
+        .ok()?;
+    key.get_value("ADUserName").ok()
+}
+
+#[cfg(not(windows))]
+fn identity_address() -> Option<String> {
+    None
+}
+
+/// Forms an address like `jane@corp.example.com` for a user logged on
+/// to a domain.
+fn domain_address(user: Option<String>, domain: Option<String>) -> Option<String> {
+    Some(format!("{}@{}", user?, domain?).to_lowercase())
+}
+
+/// Returns the email address of the current user, if it can be resolved.
+///
+/// This is the address the user signed in to Office with, as recorded in
+/// the registry by Outlook 2016 and later. Failing that, on a computer
+/// joined to a domain, an address is formed from the `USERNAME` and
+/// `USERDNSDOMAIN` environment variables, which may not match the user's
+/// actual email address.
+#[must_use]
+pub fn current_user_address() -> Option<String> {
+    identity_address()
+        .filter(|addr| is_plausible_address(addr))
+        .or_else(|| {
+            domain_address(
+                std::env::var("USERNAME").ok(),
+                std::env::var("USERDNSDOMAIN").ok(),
+            )
+        })
+}
+
+fn outlook_exe() -> io::Result<&'static str> {
+    outlook_path().ok_or_else(|| {
+        outlook_not_found();
+        io::Error::new(io::ErrorKind::NotFound, "OUTLOOK.EXE")
+    })
+}
+
+/// Called whenever OUTLOOK.EXE is needed but could not be located.
+///
+/// With the `log` feature enabled, this logs a warning naming the
+/// registry keys that were tried.
+#[inline]
+fn outlook_not_found() {
+    #[cfg(all(feature = "log", windows))]
+    log::warn!(
+        "OUTLOOK.EXE could not be located, tried HKEY_LOCAL_MACHINE\\{0} and HKEY_CURRENT_USER\\{0}",
+        OUTLOOK_SUBKEY
+    );
+    #[cfg(all(feature = "log", not(windows)))]
+    log::warn!("OUTLOOK.EXE could not be located, the registry is only available on Windows");
+}
+
+/// The characters, besides `%`, that `escape()` percent-encodes.
+pub const ESCAPE_SET: &[char] = &['"', '&', '?', '#', '+', '<', '>'];
+
+/// Escapes `s` the way `MessageBuilder` does for Outlook's `/m` switch.
+///
+/// The following are replaced:
+///
+/// 1. `%` with `%25`, first, so that later escapes are not escaped twice
+/// 2. `"` with `%22`
+/// 3. `&` with `%26`
+/// 4. `?` with `%3F`
+/// 5. `#` with `%23`
+/// 6. `+` with `%2B`
+/// 7. `<` with `%3C`
+/// 8. `>` with `%3E`
+/// 9. each line break (`\r\n`, `\r`, or `\n`) with `%0D%0A`
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(
+///     outlook_exe::escape("Q&A: 100% \"done\"?\n"),
+///     "Q%26A: 100%25 %22done%22%3F%0D%0A"
+/// );
+/// ```
+#[must_use]
+pub fn escape(s: &str) -> String {
+    escape_with(s, ESCAPE_SET)
+}
+
+/// Like `escape()`, but percent-encodes the characters of `set` instead
+/// of `ESCAPE_SET`. `%` and line breaks are always escaped.
+fn escape_with(s: &str, set: &[char]) -> String {
+    let s = s.replace("\r\n", "\n").replace('\r', "\n"); // line breaks are normalized to CRLF
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            '\n' => escaped.push_str("%0D%0A"),
+            c if set.contains(&c) => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    escaped.push_str(&format!("%{:02X}", b));
+                }
+            }
+            c => escaped.push(c),
         }
     }
+    escaped
+}
 
-    /// Adds a recipient to the email.
-    #[inline]
-    #[must_use]
-    pub fn with_recipient<S>(mut self, to: S) -> Self
-    where
-        S: Into<String>,
-    {
-        self.to.push(to.into());
-        Self {
-            subj: self.subj,
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: self.body,
-            file: self.file,
+/// Percent-encodes everything except RFC 3986 unreserved characters
+/// and `@`, normalizing line breaks to CRLF as RFC 6068 requires.
+fn uri_encode(s: &str) -> String {
+    let s = s
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n");
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                encoded.push(char::from(b));
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
         }
     }
+    encoded
+}
 
-    /// Adds a CC recipient to the email.
-    #[inline]
-    #[must_use]
-    pub fn with_recipient_cc<S>(mut self, cc: S) -> Self
-    where
-        S: Into<String>,
-    {
-        self.cc.push(cc.into());
-        Self {
-            subj: self.subj,
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: self.body,
-            file: self.file,
+/// Percent-encodes each address, and joins them with commas.
+fn encode_addresses(addrs: &[String]) -> String {
+    addrs
+        .iter()
+        .map(|addr| uri_encode(addr))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn percent_decode(s: &str) -> Result<String, ParseError> {
+    fn hex(b: Option<u8>) -> Result<u8, ParseError> {
+        match b {
+            Some(b @ b'0'..=b'9') => Ok(b - b'0'),
+            Some(b @ b'a'..=b'f') => Ok(b - b'a' + 10),
+            Some(b @ b'A'..=b'F') => Ok(b - b'A' + 10),
+            _ => Err(ParseError::InvalidPercentEncoding),
         }
     }
 
-    /// Adds a BCC recipient to the email.
-    #[inline]
-    #[must_use]
-    pub fn with_recipient_bcc<S>(mut self, bcc: S) -> Self
-    where
-        S: Into<String>,
-    {
-        self.bcc.push(bcc.into());
-        Self {
-            subj: self.subj,
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: self.body,
-            file: self.file,
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            bytes.push((hex(iter.next())? << 4) | hex(iter.next())?);
+        } else {
+            bytes.push(b);
         }
     }
+    String::from_utf8(bytes).map_err(|_| ParseError::InvalidUtf8)
+}
 
-    /// Adds a body to the email.
-    ///
-    /// This should only be called once per `MessageBuilder` instance.
-    #[inline]
-    #[must_use]
-    pub fn with_body<S>(self, body: S) -> Self
-    where
-        S: Into<String>,
-    {
-        debug_assert!(self.body.is_empty(), "Outlook body already provided");
-        Self {
-            subj: self.subj,
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: body.into(),
-            file: self.file,
+/// Decodes the percent-encoded sequences in text that may already be
+/// partially encoded, such as text copied from a URL.
+///
+/// A `%` that is not followed by two hex digits is taken literally, as in
+/// `100% done`. If the decoded text would not be valid UTF-8, the whole
+/// text is taken literally instead.
+fn decode_encoded(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        let byte = s
+            .get(i + 1..i + 3)
+            .filter(|hex| s.as_bytes()[i] == b'%' && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = byte {
+            bytes.push(byte);
+            i += 3;
+        } else {
+            bytes.push(s.as_bytes()[i]);
+            i += 1;
         }
     }
+    String::from_utf8(bytes).unwrap_or_else(|_| s.to_owned())
+}
 
-    /// Adds an attachment to the email.
-    ///
-    /// This should only be called once per `MessageBuilder` instance,
-    /// because Outlook's command-line switches only supports attaching
-    /// a single file per invocation.
-    #[inline]
-    #[must_use]
-    pub fn with_attachment<S>(self, file: S) -> Self
-    where
-        S: Into<String>,
-    {
-        debug_assert!(
-            self.file.is_empty(),
-            "Outlook's invocation switches do not support attaching multiple files"
-        );
-        Self {
-            subj: self.subj,
-            to: self.to,
-            cc: self.cc,
-            bcc: self.bcc,
-            body: self.body,
-            file: file.into(),
+/// Splits a percent-encoded list of addresses on commas and semicolons.
+fn decode_addresses(s: &str) -> Result<Vec<String>, ParseError> {
+    let mut v = Vec::new();
+    for addr in s.split([',', ';']) {
+        let addr = percent_decode(addr)?;
+        let addr = addr.trim();
+        if !addr.is_empty() {
+            v.push(addr.to_owned());
         }
     }
+    Ok(v)
+}
 
-    /// Spawns an Outlook process, and prompts the user to press "Send".
-    ///
-    /// # Errors
-    ///
-    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
-    /// be located, or if a child process cannot be spawned.
-    pub fn spawn(mut self) -> io::Result<process::Child> {
-        let mut s = String::new();
-        let mut sep = '?';
-        s.push_str(&percent_escape(&self.to.join(";")));
-        if !self.cc.is_empty() {
-            if !s.is_empty() {
-                s.push(sep);
-                sep = '&';
+/// Escapes plain text for embedding in an RTF document.
+///
+/// Backslashes and braces are escaped, line breaks become `\par`, and
+/// non-ASCII characters become `\u` control words with a `?` fallback.
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(
+///     outlook_exe::rtf_escape("{C:\\Temp} café\n"),
+///     "\\{C:\\\\Temp\\} caf\\u233?\\par\n"
+/// );
+/// ```
+#[must_use]
+pub fn rtf_escape(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
             }
-            s.push_str("cc=");
-            s.push_str(&percent_escape(&self.cc.join(";")));
-        }
-        if !self.bcc.is_empty() {
-            if !s.is_empty() {
-                s.push(sep);
-                sep = '&';
+            '\n' => escaped.push_str("\\par\n"),
+            '\t' => escaped.push_str("\\tab "),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    // RTF takes UTF-16 code units as signed 16-bit integers
+                    escaped.push_str(&format!("\\u{}?", *unit as i16));
+                }
             }
-            s.push_str("bcc=");
-            s.push_str(&percent_escape(&self.bcc.join(";")));
         }
-        if !self.subj.is_empty() {
-            if !s.is_empty() {
-                s.push(sep);
-                sep = '&';                
+    }
+    escaped
+}
+
+/// Replaces each `{key}` in `template` with its value in `vars`,
+/// leaving placeholders without a value intact.
+fn render_template(template: &str, vars: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = rest
+            .find('}')
+            .and_then(|close| vars.get(&rest[1..close]).map(|value| (close, value)));
+        if let Some((close, value)) = value {
+            rendered.push_str(value);
+            rest = &rest[close + 1..];
+        } else {
+            rendered.push('{');
+            rest = &rest[1..];
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Hard-wraps each line of `text` that is longer than `width` characters,
+/// breaking on whitespace.
+fn wrap_lines(text: &str, width: usize) -> String {
+    let mut wrapped = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+        let (content, cr) = match line.strip_suffix('\r') {
+            Some(content) => (content, "\r"),
+            None => (line, ""),
+        };
+        if content.chars().count() <= width {
+            wrapped.push_str(line);
+            continue;
+        }
+        let indent = &content[..content.len() - content.trim_start().len()];
+        wrapped.push_str(indent);
+        let mut len = indent.chars().count();
+        for (j, word) in content.split_whitespace().enumerate() {
+            let word_len = word.chars().count();
+            if j > 0 && len + 1 + word_len > width {
+                wrapped.push_str(cr);
+                wrapped.push('\n');
+                len = 0;
+            } else if j > 0 {
+                wrapped.push(' ');
+                len += 1;
             }
-            s.push_str("subject=");
-            s.push_str(&percent_escape(&self.subj));
+            wrapped.push_str(word);
+            len += word_len;
         }
-        if !self.body.is_empty() {
-            if !s.is_empty() {
-                s.push(sep);
+        wrapped.push_str(cr);
+    }
+    wrapped
+}
+
+/// Returns the extension of the file name at the end of `path`, if any.
+fn file_kind(path: &str) -> Option<&str> {
+    let name = path.rsplit(['\\', '/']).next()?;
+    match name.rfind('.') {
+        Some(i) if i > 0 && i + 1 < name.len() => Some(&name[i + 1..]),
+        _ => None,
+    }
+}
+
+/// Removes a leading UTF-8 byte order mark, which is never intended
+/// as content.
+fn strip_bom(mut s: String) -> String {
+    if s.starts_with('\u{feff}') {
+        s.drain(..'\u{feff}'.len_utf8());
+    }
+    s
+}
+
+/// Appends a `key=value` parameter to a mailto-style `/m` argument,
+/// skipping empty values.
+fn push_param(s: &mut String, key: &str, value: &str) {
+    push_escaped_param(s, key, &escape(value));
+}
+
+/// Like `push_param`, but for a value that has already been escaped.
+fn push_escaped_param(s: &mut String, key: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    s.push(if s.contains('?') { '&' } else { '?' });
+    s.push_str(key);
+    s.push('=');
+    s.push_str(value);
+}
+
+/// Percent-escapes each recipient with the characters of `set`, and
+/// joins them with `sep`.
+///
+/// Semicolons and commas within a recipient, such as in a display name,
+/// are escaped too, so that they cannot split it into two recipients.
+fn join_recipients(recipients: &[String], sep: char, set: &[char]) -> String {
+    recipients
+        .iter()
+        .map(|r| escape_with(r, set).replace(';', "%3B").replace(',', "%2C"))
+        .collect::<Vec<_>>()
+        .join(&sep.to_string())
+}
+
+/// Quotes an argument the way `process::Command` does on Windows.
+///
+/// An argument is only quoted if it is empty or contains a space or a
+/// tab, but a `"` within it is always escaped.
+fn quote_arg(arg: &str) -> String {
+    let quote = arg.is_empty() || arg.contains([' ', '\t']);
+    let mut s = String::with_capacity(arg.len() + 2);
+    if quote {
+        s.push('"');
+    }
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+        } else {
+            if c == '"' {
+                s.extend((0..=backslashes).map(|_| '\\'));
             }
-            s.push_str("body=");
-            s.push_str(&percent_escape(&self.body));
+            backslashes = 0;
         }
-        let mut a = Vec::new();
-        if !self.file.is_empty() {
-            a.push("/a");
-            self.file = percent_escape(&self.file);
-            a.push(&self.file);
-        }
-        let outlook_exe =
-            OUTLOOK_EXE.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUTLOOK.EXE"))?;
-        process::Command::new(outlook_exe)
-            .arg("/c")
-            .arg("ipm.note")
-            .arg("/m")
-            .arg(s)
-            .args(a)
-            .spawn()
+        s.push(c);
     }
+    if quote {
+        s.extend((0..backslashes).map(|_| '\\'));
+        s.push('"');
+    }
+    s
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Quotes an argument as a PowerShell single-quoted string, in which
+/// only single quotes, including typographic ones, need escaping.
+fn powershell_quote(arg: &str) -> String {
+    let mut s = String::from('\'');
+    for c in arg.chars() {
+        if let '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' = c {
+            s.push(c);
+        }
+        s.push(c);
+    }
+    s.push('\'');
+    s
+}
 
-    #[test]
-    fn message_builder() {
-        // A dumb test
-        let mb = MessageBuilder::new();
-        assert_eq!(mb.to.len(), 0);
-        assert_eq!(mb.cc.len(), 0);
-        assert_eq!(mb.bcc.len(), 0);
-        assert_eq!(mb.subj, "");
-        assert_eq!(mb.body, "");
-        assert_eq!(mb.file, "");
-        let mb = mb
-            .with_recipient("noreply@example.org")
-            .with_subject("Hello, World!")
-            .with_body("Line with spaces\nAnother line")
-            .with_attachment("C:/tmp/file.txt");
-        assert_eq!(mb.to.len(), 1);
-        assert_eq!(mb.cc.len(), 0);
-        assert_eq!(mb.bcc.len(), 0);
-        assert_eq!(mb.to[0], "noreply@example.org");
-        assert_eq!(mb.subj, "Hello, World!");
-        assert_eq!(mb.body, "Line with spaces\nAnother line");
-        assert_eq!(mb.file, "C:/tmp/file.txt");
+/// Escapes a command line for a batch file: `%` is doubled, and CMD's
+/// special characters outside of double quotes are escaped with `^`.
+fn cmd_escape(line: &str) -> String {
+    let mut s = String::with_capacity(line.len());
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '%' => s.push('%'),
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' if !quoted => s.push('^'),
+            _ => {}
+        }
+        s.push(c);
+    }
+    s
+}
+
+/// Joins an executable and its arguments into a single command line.
+fn command_line(exe: &str, args: &[String]) -> String {
+    let mut s = quote_arg(exe);
+    for arg in args {
+        s.push(' ');
+        s.push_str(&quote_arg(arg));
+    }
+    s
+}
+
+/// The importance of an Outlook item.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Importance {
+    /// Low importance.
+    Low,
+    /// Normal importance, which is Outlook's default.
+    Normal,
+    /// High importance.
+    High,
+}
+
+impl Default for Importance {
+    #[inline]
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+/// The format of the body of an email.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BodyFormat {
+    /// Plain text, which is Outlook's default.
+    Plain,
+    /// HTML, see `MessageBuilder::with_body_html()`.
+    Html,
+    /// Rich Text Format, see `MessageBuilder::with_body_rtf()`.
+    Rtf,
+}
+
+impl Default for BodyFormat {
+    #[inline]
+    fn default() -> Self {
+        Self::Plain
+    }
+}
+
+/// An error returned when a `MessageBuilder` is misused.
+///
+/// As `io::Error` is neither `Clone` nor `PartialEq`, an error wrapping
+/// `SpawnError::Spawn` is cloned and compared by its kind and message.
+#[derive(Debug)]
+pub enum BuilderError {
+    /// A subject was provided more than once.
+    SubjectAlreadySet,
+    /// A body was provided more than once.
+    BodyAlreadySet,
+    /// The email has no recipients, or a recipient is not a
+    /// plausibly-formed email address.
+    Validation(ValidationError),
+    /// The email cannot be spawned, see `MessageBuilder::try_spawn()`.
+    Spawn(SpawnError),
+}
+
+impl Clone for BuilderError {
+    fn clone(&self) -> Self {
+        match self {
+            Self::SubjectAlreadySet => Self::SubjectAlreadySet,
+            Self::BodyAlreadySet => Self::BodyAlreadySet,
+            Self::Validation(e) => Self::Validation(e.clone()),
+            Self::Spawn(e) => Self::Spawn(match e {
+                SpawnError::OutlookNotFound => SpawnError::OutlookNotFound,
+                SpawnError::CommandTooLong { len } => SpawnError::CommandTooLong { len: *len },
+                SpawnError::Spawn(e) => SpawnError::Spawn(io::Error::new(e.kind(), e.to_string())),
+            }),
+        }
+    }
+}
+
+impl PartialEq for BuilderError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::SubjectAlreadySet, Self::SubjectAlreadySet)
+            | (Self::BodyAlreadySet, Self::BodyAlreadySet) => true,
+            (Self::Validation(a), Self::Validation(b)) => a == b,
+            (Self::Spawn(a), Self::Spawn(b)) => match (a, b) {
+                (SpawnError::OutlookNotFound, SpawnError::OutlookNotFound) => true,
+                (SpawnError::CommandTooLong { len: a }, SpawnError::CommandTooLong { len: b }) => {
+                    a == b
+                }
+                (SpawnError::Spawn(a), SpawnError::Spawn(b)) => {
+                    a.kind() == b.kind() && a.to_string() == b.to_string()
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Eq for BuilderError {}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SubjectAlreadySet => f.write_str("Outlook subject already provided"),
+            Self::BodyAlreadySet => f.write_str("Outlook body already provided"),
+            Self::Validation(e) => e.fmt(f),
+            Self::Spawn(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for BuilderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::SubjectAlreadySet | Self::BodyAlreadySet => None,
+            Self::Validation(e) => Some(e),
+            Self::Spawn(e) => Some(e),
+        }
+    }
+}
+
+impl From<ValidationError> for BuilderError {
+    #[inline]
+    fn from(e: ValidationError) -> Self {
+        Self::Validation(e)
+    }
+}
+
+impl From<SpawnError> for BuilderError {
+    #[inline]
+    fn from(e: SpawnError) -> Self {
+        Self::Spawn(e)
+    }
+}
+
+/// An error returned when a `mailto:` URI cannot be parsed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseError {
+    /// The URI does not start with `mailto:`.
+    MissingScheme,
+    /// The URI contains a `%` that is not followed by two hex digits.
+    InvalidPercentEncoding,
+    /// The URI decodes to invalid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingScheme => f.write_str("URI does not start with \"mailto:\""),
+            Self::InvalidPercentEncoding => f.write_str("invalid percent-encoding in URI"),
+            Self::InvalidUtf8 => f.write_str("URI decodes to invalid UTF-8"),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// The longest command line, in UTF-16 code units, that Windows accepts
+/// when spawning a process.
+///
+/// Longer command lines would be rejected or truncated, so spawning a
+/// `MessageBuilder` whose command line exceeds this fails with
+/// `SpawnError::CommandTooLong`.
+pub const MAX_COMMAND_LEN: usize = 32_766;
+
+/// The default limit on the total size of an email's attachments in
+/// Outlook 2013 and later, in bytes.
+///
+/// Outlook refuses to attach files beyond this limit, which an
+/// administrator may have set differently.
+pub const MAX_ATTACHMENT_SIZE: u64 = 20 * 1024 * 1024;
+
+/// An error returned when an Outlook process cannot be spawned.
+#[derive(Debug)]
+pub enum SpawnError {
+    /// OUTLOOK.EXE could not be located.
+    OutlookNotFound,
+    /// The command line is longer than `MAX_COMMAND_LEN`.
+    CommandTooLong {
+        /// The length of the command line, in UTF-16 code units.
+        len: usize,
+    },
+    /// A child process could not be spawned.
+    Spawn(io::Error),
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutlookNotFound => f.write_str("OUTLOOK.EXE could not be located"),
+            Self::CommandTooLong { len } => write!(
+                f,
+                "command line is {} characters long, exceeding the limit of {}",
+                len, MAX_COMMAND_LEN
+            ),
+            Self::Spawn(e) => write!(f, "failed to spawn OUTLOOK.EXE: {}", e),
+        }
+    }
+}
+
+impl error::Error for SpawnError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::OutlookNotFound | Self::CommandTooLong { .. } => None,
+            Self::Spawn(e) => Some(e),
+        }
+    }
+}
+
+impl From<SpawnError> for io::Error {
+    fn from(e: SpawnError) -> Self {
+        match e {
+            SpawnError::OutlookNotFound => Self::new(io::ErrorKind::NotFound, "OUTLOOK.EXE"),
+            SpawnError::CommandTooLong { .. } => Self::new(io::ErrorKind::InvalidInput, e),
+            SpawnError::Spawn(e) => e,
+        }
+    }
+}
+
+/// An error returned when an email cannot be sent through Outlook's COM
+/// automation interface.
+///
+/// This requires the `com` feature.
+#[cfg(feature = "com")]
+#[derive(Debug)]
+pub enum ComError {
+    /// Sending through COM does not support part of the email, or the
+    /// platform.
+    Unsupported(&'static str),
+    /// A COM call failed.
+    Call {
+        /// The name of the function, method, or property called.
+        name: &'static str,
+        /// The `HRESULT` returned by the call.
+        code: i32,
+    },
+}
+
+#[cfg(feature = "com")]
+impl fmt::Display for ComError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(what) => write!(f, "sending via COM does not support {}", what),
+            Self::Call { name, code } => {
+                write!(f, "COM call {} failed with HRESULT {:#010X}", name, code)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "com")]
+impl error::Error for ComError {}
+
+/// A recipient field of a `MessageBuilder`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RecipientField {
+    /// The direct recipients.
+    To,
+    /// The CC recipients.
+    Cc,
+    /// The BCC recipients.
+    Bcc,
+}
+
+impl fmt::Display for RecipientField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::To => f.write_str("To"),
+            Self::Cc => f.write_str("CC"),
+            Self::Bcc => f.write_str("BCC"),
+        }
+    }
+}
+
+/// An error returned when a `MessageBuilder` fails validation.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ValidationError {
+    /// A recipient is not a plausibly-formed email address.
+    InvalidAddress {
+        /// The field containing the recipient.
+        field: RecipientField,
+        /// The recipient, as it was provided.
+        recipient: String,
+    },
+    /// The email has no recipients, CC, or BCC.
+    NoRecipients,
+    /// An attachment does not exist, holding its path as it was provided.
+    AttachmentMissing(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAddress { field, recipient } => {
+                write!(f, "invalid {} address: {:?}", field, recipient)
+            }
+            Self::NoRecipients => f.write_str("Outlook email has no recipients"),
+            Self::AttachmentMissing(path) => write!(f, "attachment not found: {:?}", path),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// A recipient of an email, with an optional display name.
+///
+/// A recipient can be converted from a string, which is passed to Outlook
+/// as it is, so it may already contain a display name.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recipient {
+    name: Option<String>,
+    email: String,
+}
+
+impl Recipient {
+    /// Creates a recipient without a display name.
+    #[inline]
+    #[must_use]
+    pub fn new<S>(email: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: None,
+            email: email.into(),
+        }
+    }
+
+    /// Creates a recipient with a display name.
+    #[inline]
+    #[must_use]
+    pub fn named<N, E>(name: N, email: E) -> Self
+    where
+        N: Into<String>,
+        E: Into<String>,
+    {
+        Self {
+            name: Some(name.into()),
+            email: email.into(),
+        }
+    }
+
+    /// Returns the display name of the recipient, if any.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the email address of the recipient.
+    #[inline]
+    #[must_use]
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+}
+
+impl From<&str> for Recipient {
+    #[inline]
+    fn from(email: &str) -> Self {
+        Self::new(email)
+    }
+}
+
+impl From<&String> for Recipient {
+    #[inline]
+    fn from(email: &String) -> Self {
+        Self::new(email.as_str())
+    }
+}
+
+impl From<String> for Recipient {
+    #[inline]
+    fn from(email: String) -> Self {
+        Self::new(email)
+    }
+}
+
+impl From<Recipient> for String {
+    /// Formats the recipient the way Outlook expects, see `Display`.
+    #[inline]
+    fn from(recipient: Recipient) -> Self {
+        match recipient.name {
+            Some(name) => named_recipient(&name, &recipient.email),
+            None => recipient.email,
+        }
+    }
+}
+
+impl fmt::Display for Recipient {
+    /// Formats the recipient the way Outlook expects, e.g.
+    /// `"Jane Doe" <jane@example.org>`, or just the address if it has no
+    /// display name.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => f.write_str(&named_recipient(name, &self.email)),
+            None => f.write_str(&self.email),
+        }
+    }
+}
+
+/// Formats a recipient with a display name, e.g. `"Jane Doe" <jane@example.org>`.
+fn named_recipient(name: &str, email: &str) -> String {
+    let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\" <{}>", name, email)
+}
+
+/// Returns the address of a recipient, stripping any display name
+/// from forms like `Jane Doe <jane@example.org>`.
+fn address_of(recipient: &str) -> &str {
+    let recipient = recipient.trim();
+    match (recipient.rfind('<'), recipient.strip_suffix('>')) {
+        (Some(i), Some(rest)) => &rest[i + 1..],
+        _ => recipient,
+    }
+}
+
+/// Checks that a recipient is plausibly an email address, allowing
+/// display-name forms like `Jane Doe <jane@example.org>`.
+fn is_plausible_address(recipient: &str) -> bool {
+    let addr = address_of(recipient);
+    match addr.rfind('@') {
+        Some(i) => {
+            let (local, domain) = (&addr[..i], &addr[i + 1..]);
+            !local.is_empty() && !domain.is_empty() && !addr.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// The `MessageBuilder` type, for drafting Outlook email messages.
+///
+/// With the `serde` feature enabled, `MessageBuilder` implements
+/// `Serialize` and `Deserialize`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageBuilder {
+    subj: Cow<'static, str>,
+    to: Vec<String>,
+    cc: Vec<String>,
+    bcc: Vec<String>,
+    body: Cow<'static, str>,
+    files: Vec<String>,
+    exe: Option<PathBuf>,
+    profile: String,
+    recycle: bool,
+    importance: Importance,
+    signature: String,
+    format: BodyFormat,
+    class: String,
+    nopreview: bool,
+    separator: Option<char>,
+    background: bool,
+    from: String,
+    followup: bool,
+    raw: Vec<String>,
+    dir: Option<PathBuf>,
+    env: Vec<(OsString, OsString)>,
+    env_clear: bool,
+    embedding: bool,
+    subj_prefix: String,
+    account: String,
+    escape_set: Option<Vec<char>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    subj_repeated: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    body_repeated: bool,
+}
+
+impl MessageBuilder {
+    /// Creates a new `MessageBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            subj: Cow::Borrowed(""),
+            to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            body: Cow::Borrowed(""),
+            files: Vec::new(),
+            exe: None,
+            profile: String::new(),
+            recycle: false,
+            importance: Importance::Normal,
+            signature: String::new(),
+            format: BodyFormat::Plain,
+            class: String::new(),
+            nopreview: false,
+            separator: None,
+            background: false,
+            from: String::new(),
+            followup: false,
+            raw: Vec::new(),
+            dir: None,
+            env: Vec::new(),
+            env_clear: false,
+            embedding: false,
+            subj_prefix: String::new(),
+            account: String::new(),
+            escape_set: None,
+            subj_repeated: false,
+            body_repeated: false,
+        }
+    }
+
+    /// Creates a new `MessageBuilder` from a `mailto:` URI.
+    ///
+    /// The `to`, `cc`, `bcc`, `subject`, and `body` query parameters
+    /// are percent-decoded, and any other parameters are ignored.
+    /// Multiple recipients may be separated by commas or semicolons.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(ParseError)` if the URI does not start with
+    /// `mailto:`, or if it contains invalid percent-encoding.
+    pub fn from_mailto(uri: &str) -> Result<Self, ParseError> {
+        const SCHEME: &str = "mailto:";
+
+        if !matches!(uri.get(..SCHEME.len()), Some(s) if s.eq_ignore_ascii_case(SCHEME)) {
+            return Err(ParseError::MissingScheme);
+        }
+        let uri = &uri[SCHEME.len()..];
+        let (to, query) = match uri.find('?') {
+            Some(i) => (&uri[..i], &uri[i + 1..]),
+            None => (uri, ""),
+        };
+        let mut mb = Self::new();
+        mb.to = decode_addresses(to)?;
+        for param in query.split('&') {
+            let (key, value) = match param.find('=') {
+                Some(i) => (&param[..i], &param[i + 1..]),
+                None => (param, ""),
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "to" => mb.to.extend(decode_addresses(value)?),
+                "cc" => mb.cc.extend(decode_addresses(value)?),
+                "bcc" => mb.bcc.extend(decode_addresses(value)?),
+                "subject" => mb.subj = percent_decode(value)?.into(),
+                "body" => mb.body = percent_decode(value)?.into(),
+                "x-priority" => match percent_decode(value)?.trim_start().chars().next() {
+                    Some('1') | Some('2') => mb.importance = Importance::High,
+                    Some('4') | Some('5') => mb.importance = Importance::Low,
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        Ok(mb)
+    }
+
+    /// Sets the subject of the email.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    pub fn set_subject<S>(&mut self, subj: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.subj_repeated |= !self.subj.is_empty();
+        self.subj = Cow::Owned(subj.into());
+        self
+    }
+
+    /// Adds a subject to the email.
+    ///
+    /// The subject is always copied into a `String`; use
+    /// `with_static_subject()` to avoid allocating for a string literal.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_subject<S>(mut self, subj: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.set_subject(subj);
+        self
+    }
+
+    /// Adds a static subject to the email, without allocating.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_static_subject(mut self, subj: &'static str) -> Self {
+        self.subj_repeated |= !self.subj.is_empty();
+        self.subj = Cow::Borrowed(subj);
+        self
+    }
+
+    /// Sets a prefix, such as `"[PROJ-123] "`, to prepend to the subject
+    /// of the email.
+    ///
+    /// The prefix is prepended when the email is spawned or formatted, so
+    /// it may be set before or after the subject. If no subject is set,
+    /// the prefix alone becomes the subject. Calling this again replaces
+    /// the prefix.
+    #[inline]
+    #[must_use]
+    pub fn with_subject_prefix<S>(self, prefix: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: prefix.into(),
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Adds a subject to the email, truncated to at most `max_len`
+    /// characters.
+    ///
+    /// A truncated subject ends with an ellipsis (`…`), which counts
+    /// towards `max_len`.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_subject_truncated<S>(self, subj: S, max_len: usize) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut subj = subj.into();
+        if let Some((i, _)) = subj.char_indices().nth(max_len) {
+            let end = subj[..i].char_indices().next_back().map_or(0, |(j, _)| j);
+            subj.truncate(end);
+            if max_len > 0 {
+                subj.push('…');
+            }
+        }
+        self.with_subject(subj)
+    }
+
+    /// Adds a subject to the email that may already be percent-encoded,
+    /// such as `"Hello%20World"`, decoding it first so that it is only
+    /// encoded once.
+    ///
+    /// Only `%` followed by two hex digits is decoded, and the subject is
+    /// taken literally if it would not decode to valid UTF-8.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_subject_encoded<S>(self, subj: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.with_subject(decode_encoded(subj.as_ref()))
+    }
+
+    /// Adds a subject to the email, failing if one was already provided.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(BuilderError::SubjectAlreadySet)` if a subject
+    /// was already provided.
+    #[inline]
+    pub fn try_with_subject<S>(self, subj: S) -> Result<Self, BuilderError>
+    where
+        S: Into<String>,
+    {
+        if self.subj.is_empty() {
+            Ok(self.with_subject(subj))
+        } else {
+            Err(BuilderError::SubjectAlreadySet)
+        }
+    }
+
+    /// Adds a recipient to the email, in place.
+    #[inline]
+    pub fn push_recipient<S>(&mut self, to: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.to.push(to.into());
+        self
+    }
+
+    /// Adds a recipient to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipient<S>(mut self, to: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.push_recipient(to);
+        self
+    }
+
+    /// Adds a recipient with a display name to the email.
+    ///
+    /// Outlook resolves recipients written in the RFC 5322 form
+    /// `"Jane Doe" <jane@example.org>`, so the recipient is formatted
+    /// that way, with any quotes in the display name escaped.
+    #[inline]
+    #[must_use]
+    pub fn with_named_recipient<N, E>(self, name: N, email: E) -> Self
+    where
+        N: Into<String>,
+        E: Into<String>,
+    {
+        self.with_recipient(named_recipient(&name.into(), &email.into()))
+    }
+
+    /// Adds the recipients in a list separated by commas or semicolons,
+    /// such as `"a@example.com, b@example.com"`.
+    ///
+    /// Whitespace around each recipient is trimmed, and empty entries are
+    /// ignored.
+    #[inline]
+    #[must_use]
+    pub fn with_recipients_str<S>(self, to: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.with_recipients(
+            to.as_ref()
+                .split([',', ';'])
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty()),
+        )
+    }
+
+    /// Adds multiple recipients to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipients<I, S>(mut self, to: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.to.extend(to.into_iter().map(Into::into));
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Adds a CC recipient to the email, in place.
+    #[inline]
+    pub fn push_recipient_cc<S>(&mut self, cc: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.cc.push(cc.into());
+        self
+    }
+
+    /// Adds a CC recipient to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipient_cc<S>(mut self, cc: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.push_recipient_cc(cc);
+        self
+    }
+
+    /// Adds the current user as a CC recipient, so that they keep a copy
+    /// of the email.
+    ///
+    /// The address is resolved with `current_user_address()`. If it cannot
+    /// be resolved, the email is left unchanged.
+    #[inline]
+    #[must_use]
+    pub fn cc_self(self) -> Self {
+        self.cc_resolved(current_user_address)
+    }
+
+    fn cc_resolved<F>(self, resolve: F) -> Self
+    where
+        F: FnOnce() -> Option<String>,
+    {
+        match resolve() {
+            Some(addr) => self.with_recipient_cc(addr),
+            None => self,
+        }
+    }
+
+    /// Adds multiple CC recipients to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipients_cc<I, S>(mut self, cc: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cc.extend(cc.into_iter().map(Into::into));
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Adds a BCC recipient to the email, in place.
+    #[inline]
+    pub fn push_recipient_bcc<S>(&mut self, bcc: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.bcc.push(bcc.into());
+        self
+    }
+
+    /// Adds a BCC recipient to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipient_bcc<S>(mut self, bcc: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.push_recipient_bcc(bcc);
+        self
+    }
+
+    /// Adds a records mailbox as a BCC recipient, for keeping a copy of
+    /// the email where compliance requires it.
+    ///
+    /// This is the same as `with_recipient_bcc()`, but makes the intent
+    /// clear to reviewers.
+    #[inline]
+    #[must_use]
+    pub fn with_records_copy<S>(self, addr: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.with_recipient_bcc(addr)
+    }
+
+    /// Adds multiple BCC recipients to the email.
+    #[inline]
+    #[must_use]
+    pub fn with_recipients_bcc<I, S>(mut self, bcc: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.bcc.extend(bcc.into_iter().map(Into::into));
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets the body of the email.
+    ///
+    /// A leading byte order mark is removed.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    pub fn set_body<S>(&mut self, body: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.body_repeated |= !self.body.is_empty();
+        self.body = Cow::Owned(strip_bom(body.into()));
+        self
+    }
+
+    /// Adds a body to the email.
+    ///
+    /// The body is always copied into a `String`; use `with_static_body()`
+    /// to avoid allocating for a string literal.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body<S>(mut self, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.set_body(body);
+        self
+    }
+
+    /// Adds a body to the email, rendered from `template` by replacing
+    /// each `{key}` with its value in `vars`.
+    ///
+    /// Placeholders without a value in `vars` are left intact. The
+    /// rendered body is escaped like any other body.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body_template(self, template: &str, vars: &HashMap<String, String>) -> Self {
+        self.with_body(render_template(template, vars))
+    }
+
+    /// Adds a body to the email that may already be percent-encoded,
+    /// decoding it first, see `with_subject_encoded()`.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body_encoded<S>(self, body: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.with_body(decode_encoded(body.as_ref()))
+    }
+
+    /// Adds a static body to the email, without allocating.
+    ///
+    /// A leading byte order mark is removed.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_static_body(mut self, body: &'static str) -> Self {
+        self.body_repeated |= !self.body.is_empty();
+        self.body = Cow::Borrowed(body.strip_prefix('\u{feff}').unwrap_or(body));
+        self
+    }
+
+    /// Adds a body to the email, hard-wrapped to `width` characters for
+    /// mail clients that do not wrap long lines.
+    ///
+    /// Lines are only broken on whitespace, so a word longer than `width`
+    /// is kept whole on a line of its own. Existing line breaks are kept,
+    /// and lines that already fit are left as they are. In lines that are
+    /// wrapped, leading indentation is kept on the first line only, runs
+    /// of whitespace are collapsed to a single space, and every new line
+    /// break repeats the line's own ending, `\r\n` or `\n`.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body_wrapped<S>(self, body: S, width: usize) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.with_body(wrap_lines(body.as_ref(), width))
+    }
+
+    /// Adds a body to the email, failing if one was already provided.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(BuilderError::BodyAlreadySet)` if a body
+    /// was already provided.
+    #[inline]
+    pub fn try_with_body<S>(self, body: S) -> Result<Self, BuilderError>
+    where
+        S: Into<String>,
+    {
+        if self.body.is_empty() {
+            Ok(self.with_body(body))
+        } else {
+            Err(BuilderError::BodyAlreadySet)
+        }
+    }
+
+    /// Adds a body to the email, read from the file at `path`.
+    ///
+    /// A leading byte order mark, as saved by Notepad, is removed.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if the file cannot be read as UTF-8.
+    #[inline]
+    pub fn with_body_from_file<P>(self, path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self.with_body(fs::read_to_string(path)?))
+    }
+
+    /// Adds an HTML body to the email.
+    ///
+    /// Outlook's `/m` switch has no way to mark the body as HTML, so
+    /// the markup is passed as plain text, and Outlook displays it
+    /// literally. The body is still marked as HTML, see `is_html()`.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body_html<S>(self, html: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: Cow::Owned(strip_bom(html.into())),
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: BodyFormat::Html,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated || !self.body.is_empty(),
+        }
+    }
+
+    /// Adds an RTF body to the email.
+    ///
+    /// Outlook's `/m` switch has no way to mark the body as RTF, so the
+    /// markup is passed as plain text, and Outlook displays it literally.
+    /// The `/t` switch is no alternative, as it only opens templates
+    /// saved by Outlook as `.oft` files. The body is still marked as RTF,
+    /// see `body_format()`. Use `rtf_escape()` to embed plain text in RTF.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body_rtf<S>(self, rtf: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: Cow::Owned(strip_bom(rtf.into())),
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: BodyFormat::Rtf,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated || !self.body.is_empty(),
+        }
+    }
+
+    /// Appends to the body of the email, separating it from any
+    /// existing body with a newline.
+    #[inline]
+    #[must_use]
+    pub fn append_body<S>(mut self, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        if !self.body.is_empty() {
+            self.body.to_mut().push('\n');
+        }
+        self.body.to_mut().push_str(&body.into());
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Adds a signature to the email.
+    ///
+    /// When spawned, the signature is appended to the body after a
+    /// blank line.
+    ///
+    /// This should only be called once per `MessageBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_signature<S>(self, signature: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(
+            self.signature.is_empty(),
+            "Outlook signature already provided"
+        );
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: signature.into(),
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Adds an attachment to the email, in place.
+    ///
+    /// See `with_attachment()` for how attachments are passed to Outlook.
+    #[inline]
+    pub fn push_attachment<S>(&mut self, file: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.files.push(file.into());
+        self
+    }
+
+    /// Adds an attachment to the email.
+    ///
+    /// Each attachment is passed to Outlook with its own `/a` switch.
+    /// Some versions of Outlook only honor the first of these, so the
+    /// remaining attachments may be ignored.
+    ///
+    /// The path is passed verbatim as a single argument, so it may
+    /// contain spaces, and is not percent-escaped. This includes UNC
+    /// paths to network shares, such as `\\server\share\file.txt`.
+    #[inline]
+    #[must_use]
+    pub fn with_attachment<S>(mut self, file: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.push_attachment(file);
+        self
+    }
+
+    /// Adds an attachment to the email from a path.
+    ///
+    /// This behaves like `with_attachment()`. On Windows, forward slashes
+    /// are converted to the backslashes Outlook expects, and verbatim
+    /// paths, as returned by `fs::canonicalize()`, are converted to their
+    /// plain form, e.g. `\\?\UNC\server\share` to `\\server\share`.
+    /// Paths that are not valid Unicode are converted lossily, replacing
+    /// invalid sequences with `U+FFFD`.
+    #[inline]
+    #[must_use]
+    pub fn with_attachment_path<P>(self, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let file = path.as_ref().to_string_lossy();
+        let file = if cfg!(windows) {
+            let file = file.replace('/', "\\");
+            if let Some(rest) = file.strip_prefix(r"\\?\UNC\") {
+                format!(r"\\{}", rest)
+            } else if let Some(rest) = file.strip_prefix(r"\\?\") {
+                rest.to_owned()
+            } else {
+                file
+            }
+        } else {
+            file.into_owned()
+        };
+        self.with_attachment(file)
+    }
+
+    /// Sets the separator used to join multiple recipients, which is
+    /// `';'` by default.
+    ///
+    /// Some Outlook installs, such as those using a European list
+    /// separator, expect `','` instead.
+    #[inline]
+    #[must_use]
+    pub const fn with_recipient_separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Removes all recipients from the email.
+    #[inline]
+    #[must_use]
+    pub fn clear_recipients(self) -> Self {
+        Self {
+            subj: self.subj,
+            to: Vec::new(),
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Removes all CC recipients from the email.
+    #[inline]
+    #[must_use]
+    pub fn clear_cc(self) -> Self {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: Vec::new(),
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Removes all BCC recipients from the email.
+    #[inline]
+    #[must_use]
+    pub fn clear_bcc(self) -> Self {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: Vec::new(),
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Removes all attachments from the email.
+    #[inline]
+    #[must_use]
+    pub fn clear_attachments(self) -> Self {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: Vec::new(),
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Merges `other` into this email, so that a common template can be
+    /// layered with per-message options.
+    ///
+    /// The recipient, CC, and BCC lists and the raw arguments of `other`
+    /// are appended to those of this email. For every other option, the
+    /// value of `other` is taken when it is set, and this email's value
+    /// is kept otherwise:
+    ///
+    /// - text options (subject, subject prefix, body, signature, profile,
+    ///   account, item class, sender) are set when non-empty, and the
+    ///   body brings its format along with it;
+    /// - the attachments, as a whole, are set when `other` has any;
+    /// - the executable, working directory, recipient separator, and
+    ///   escape set are set when `Some`;
+    /// - the importance is set when it is not `Importance::Normal`;
+    /// - `recycle`, `no_preview`, `embedding`, `background`, and the
+    ///   follow-up flag are enabled if they are enabled on either email;
+    /// - environment variables are applied as if `other`'s were set
+    ///   after this email's, including `env_clear()`.
+    #[must_use]
+    pub fn merge(mut self, other: Self) -> Self {
+        fn pick<T>(mine: T, theirs: T) -> T
+        where
+            T: AsRef<str>,
+        {
+            if theirs.as_ref().is_empty() {
+                mine
+            } else {
+                theirs
+            }
+        }
+
+        self.to.extend(other.to);
+        self.cc.extend(other.cc);
+        self.bcc.extend(other.bcc);
+        self.raw.extend(other.raw);
+        if other.env_clear {
+            self.env.clear();
+        }
+        self.env.extend(other.env);
+        let format = if other.body.is_empty() {
+            self.format
+        } else {
+            other.format
+        };
+        Self {
+            subj: pick(self.subj, other.subj),
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: pick(self.body, other.body),
+            files: if other.files.is_empty() {
+                self.files
+            } else {
+                other.files
+            },
+            exe: other.exe.or(self.exe),
+            profile: pick(self.profile, other.profile),
+            recycle: self.recycle || other.recycle,
+            importance: if other.importance == Importance::Normal {
+                self.importance
+            } else {
+                other.importance
+            },
+            signature: pick(self.signature, other.signature),
+            format,
+            class: pick(self.class, other.class),
+            nopreview: self.nopreview || other.nopreview,
+            separator: other.separator.or(self.separator),
+            background: self.background || other.background,
+            from: pick(self.from, other.from),
+            followup: self.followup || other.followup,
+            raw: self.raw,
+            dir: other.dir.or(self.dir),
+            env: self.env,
+            env_clear: self.env_clear || other.env_clear,
+            embedding: self.embedding || other.embedding,
+            subj_prefix: pick(self.subj_prefix, other.subj_prefix),
+            account: pick(self.account, other.account),
+            escape_set: other.escape_set.or(self.escape_set),
+            subj_repeated: self.subj_repeated || other.subj_repeated,
+            body_repeated: self.body_repeated || other.body_repeated,
+        }
+    }
+
+    /// Removes duplicate recipients within and across the recipient,
+    /// CC, and BCC lists.
+    ///
+    /// Addresses are compared case-insensitively, ignoring display
+    /// names. The first appearance of each address is kept, with the
+    /// recipients taking precedence over CC, and CC over BCC.
+    #[must_use]
+    pub fn dedup_recipients(mut self) -> Self {
+        let mut seen = HashSet::new();
+        for list in [&mut self.to, &mut self.cc, &mut self.bcc].iter_mut() {
+            list.retain(|r| seen.insert(address_of(r).to_lowercase()));
+        }
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sorts the recipient, CC, and BCC lists case-insensitively, so
+    /// that the output of `build_command()` and `to_mailto()` does not
+    /// depend on the order in which recipients were added.
+    ///
+    /// Recipients are compared by their full text, including any display
+    /// name, and the sort is stable.
+    #[must_use]
+    pub fn sort_recipients(mut self) -> Self {
+        for list in [&mut self.to, &mut self.cc, &mut self.bcc].iter_mut() {
+            list.sort_by_cached_key(|r| r.to_lowercase());
+        }
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets the item class passed to Outlook's `/c` switch,
+    /// e.g. `"ipm.post"`.
+    ///
+    /// When no item class is set, `"ipm.note"` is used.
+    #[inline]
+    #[must_use]
+    pub fn with_item_class<S>(self, class: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: class.into(),
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets the Outlook profile to launch with.
+    ///
+    /// When no profile is set, Outlook uses the default profile.
+    /// Outlook expects `/profile` before its other switches, so it is
+    /// passed first.
+    #[inline]
+    #[must_use]
+    pub fn with_profile<S>(self, profile: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: profile.into(),
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets whether to open the email in an already-running Outlook
+    /// instance with `/recycle`, rather than starting a new one.
+    ///
+    /// This is off by default. When enabled, the spawned process may
+    /// hand the email over to the running instance and exit almost
+    /// immediately.
+    #[inline]
+    #[must_use]
+    pub const fn recycle(mut self, recycle: bool) -> Self {
+        self.recycle = recycle;
+        self
+    }
+
+    /// Sets whether to launch Outlook with `/embedding`, which starts it
+    /// as an automation server without its full user interface.
+    ///
+    /// This is off by default. The switch is passed after the other
+    /// switches, except for raw arguments. Outlook started this way may
+    /// not show the compose window at all, so this is only useful when
+    /// an automation client will drive Outlook afterwards.
+    #[inline]
+    #[must_use]
+    pub const fn embedding(mut self, embedding: bool) -> Self {
+        self.embedding = embedding;
+        self
+    }
+
+    /// Sets whether to launch Outlook with `/nopreview`, which turns
+    /// off the reading pane for a faster start.
+    ///
+    /// This is off by default.
+    #[inline]
+    #[must_use]
+    pub const fn no_preview(mut self, nopreview: bool) -> Self {
+        self.nopreview = nopreview;
+        self
+    }
+
+    /// Sets whether to launch Outlook in the background.
+    ///
+    /// This is off by default. On Windows, a background Outlook process
+    /// is created with `DETACHED_PROCESS` and `CREATE_NEW_PROCESS_GROUP`,
+    /// so it is detached from the calling process's console and is
+    /// unaffected by Ctrl+C sent to it. On other platforms, this has no
+    /// effect.
+    ///
+    /// These flags do not change how the compose window is shown: no
+    /// show-window hint is passed, so Outlook still activates its window
+    /// and may take focus.
+    #[inline]
+    #[must_use]
+    pub const fn background(mut self, background: bool) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Sets the address to send the email from.
+    ///
+    /// Outlook's invocation switches cannot set the sender, so it is not
+    /// passed to OUTLOOK.EXE, and the email opens with the profile's
+    /// default account. To send from a shared mailbox, use
+    /// `with_profile()` with a profile for that mailbox.
+    #[inline]
+    #[must_use]
+    pub fn with_from<S>(self, from: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: from.into(),
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets whether the email should be flagged for follow-up.
+    ///
+    /// Outlook's invocation switches cannot flag an email, so this may
+    /// not take effect: the flag is not passed to OUTLOOK.EXE, and the
+    /// email opens unflagged.
+    #[inline]
+    #[must_use]
+    pub const fn with_followup_flag(mut self, followup: bool) -> Self {
+        self.followup = followup;
+        self
+    }
+
+    /// Sets the priority of the email, which is the same as its
+    /// importance, see `with_importance()`.
+    ///
+    /// The priority is carried by the `X-Priority` header of
+    /// `to_mailto()`, but cannot be passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub const fn with_priority(self, priority: Importance) -> Self {
+        self.with_importance(priority)
+    }
+
+    /// Sets the account to send the email from, by its display name, when
+    /// the Outlook profile has several accounts.
+    ///
+    /// Outlook's invocation switches cannot select an account, so it is
+    /// not passed to OUTLOOK.EXE, and the email opens with the profile's
+    /// default account. To send from another account, use `with_profile()`
+    /// with a profile whose default account it is.
+    #[inline]
+    #[must_use]
+    pub fn with_account<S>(self, account: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: account.into(),
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets the characters, besides `%`, that are percent-encoded in
+    /// the `/m` argument, replacing `ESCAPE_SET`, for Outlook versions
+    /// that mishandle other characters.
+    ///
+    /// `%` is always escaped first, and line breaks are always converted
+    /// to `%0D%0A`. To extend the default set, include `ESCAPE_SET` in
+    /// `chars`. This does not affect `to_mailto()`, which percent-encodes
+    /// everything but unreserved characters.
+    #[inline]
+    #[must_use]
+    pub fn with_escape_set(self, chars: &[char]) -> Self {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: Some(chars.to_vec()),
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets the importance of the email.
+    ///
+    /// Outlook's invocation switches cannot set the importance, so it
+    /// is not passed to OUTLOOK.EXE, and the email opens with normal
+    /// importance.
+    #[inline]
+    #[must_use]
+    pub const fn with_importance(mut self, importance: Importance) -> Self {
+        self.importance = importance;
+        self
+    }
+
+    /// Adds an extra argument to pass to OUTLOOK.EXE, such as `/safe`,
+    /// after the arguments the builder generates.
+    ///
+    /// The argument is passed verbatim, so a misplaced or malformed
+    /// switch can break the invocation.
+    #[inline]
+    #[must_use]
+    pub fn with_raw_arg<S>(mut self, arg: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.raw.push(arg.into());
+        self
+    }
+
+    /// Adds extra arguments to pass to OUTLOOK.EXE, see `with_raw_arg()`.
+    #[inline]
+    #[must_use]
+    pub fn with_raw_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.raw.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the working directory of the Outlook process, against which
+    /// relative attachment paths are resolved.
+    ///
+    /// When no directory is set, the process inherits the caller's.
+    #[inline]
+    #[must_use]
+    pub fn with_current_dir<P>(self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: self.exe,
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: Some(dir.into()),
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Sets an environment variable for the Outlook process.
+    ///
+    /// When no variables are set, the process inherits the caller's
+    /// environment.
+    #[inline]
+    #[must_use]
+    pub fn with_env<K, V>(mut self, key: K, val: V) -> Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.env
+            .push((key.as_ref().to_owned(), val.as_ref().to_owned()));
+        self
+    }
+
+    /// Clears the environment of the Outlook process, including any
+    /// variables set so far with `with_env()`.
+    #[inline]
+    #[must_use]
+    pub fn env_clear(mut self) -> Self {
+        self.env.clear();
+        self.env_clear = true;
+        self
+    }
+
+    /// Sets the Outlook executable to launch.
+    ///
+    /// This takes precedence over the OUTLOOK.EXE path found in the
+    /// registry, which is only consulted when no executable is set.
+    #[inline]
+    #[must_use]
+    pub fn with_executable<P>(self, exe: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            subj: self.subj,
+            to: self.to,
+            cc: self.cc,
+            bcc: self.bcc,
+            body: self.body,
+            files: self.files,
+            exe: Some(exe.into()),
+            profile: self.profile,
+            recycle: self.recycle,
+            importance: self.importance,
+            signature: self.signature,
+            format: self.format,
+            class: self.class,
+            nopreview: self.nopreview,
+            separator: self.separator,
+            background: self.background,
+            from: self.from,
+            followup: self.followup,
+            raw: self.raw,
+            dir: self.dir,
+            env: self.env,
+            env_clear: self.env_clear,
+            embedding: self.embedding,
+            subj_prefix: self.subj_prefix,
+            account: self.account,
+            escape_set: self.escape_set,
+            subj_repeated: self.subj_repeated,
+            body_repeated: self.body_repeated,
+        }
+    }
+
+    /// Returns the subject of the email.
+    #[inline]
+    #[must_use]
+    pub fn subject(&self) -> &str {
+        &self.subj
+    }
+
+    /// Returns the recipients of the email.
+    #[inline]
+    #[must_use]
+    pub fn recipients(&self) -> &[String] {
+        &self.to
+    }
+
+    /// Returns the CC recipients of the email.
+    #[inline]
+    #[must_use]
+    pub fn cc(&self) -> &[String] {
+        &self.cc
+    }
+
+    /// Returns the BCC recipients of the email.
+    #[inline]
+    #[must_use]
+    pub fn bcc(&self) -> &[String] {
+        &self.bcc
+    }
+
+    /// Returns an iterator over the recipients, CC, and BCC recipients of
+    /// the email, in that order.
+    #[inline]
+    pub fn all_recipients(&self) -> impl Iterator<Item = &str> + '_ {
+        self.to
+            .iter()
+            .chain(&self.cc)
+            .chain(&self.bcc)
+            .map(String::as_str)
+    }
+
+    /// Returns the body of the email.
+    #[inline]
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Returns whether the body of the email is HTML.
+    #[inline]
+    #[must_use]
+    pub const fn is_html(&self) -> bool {
+        matches!(self.format, BodyFormat::Html)
+    }
+
+    /// Returns the format of the body of the email.
+    #[inline]
+    #[must_use]
+    pub const fn body_format(&self) -> BodyFormat {
+        self.format
+    }
+
+    /// Returns the attachments of the email.
+    #[inline]
+    #[must_use]
+    pub fn attachments(&self) -> &[String] {
+        &self.files
+    }
+
+    /// Returns the file extension of the first attachment, e.g. `"pdf"`,
+    /// for showing its file type.
+    ///
+    /// Returns `None` if there are no attachments, or if the first one
+    /// has no extension. Use `attachment_kinds()` for every attachment.
+    #[inline]
+    #[must_use]
+    pub fn attachment_kind(&self) -> Option<&str> {
+        self.attachment_kinds().next().flatten()
+    }
+
+    /// Returns the file extension of each attachment, e.g. `"pdf"`, for
+    /// showing its file type, in the order the attachments were added.
+    ///
+    /// An attachment with no extension yields `None`. Both `\` and `/`
+    /// separate directories, so an extension is never taken from a
+    /// directory name.
+    #[inline]
+    pub fn attachment_kinds(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        self.files.iter().map(|file| file_kind(file))
+    }
+
+    /// Returns the account to send the email from.
+    #[inline]
+    #[must_use]
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    /// Returns the characters, besides `%`, that are percent-encoded in
+    /// the `/m` argument, see `with_escape_set()`.
+    #[inline]
+    #[must_use]
+    pub fn escape_set(&self) -> &[char] {
+        self.escape_set.as_deref().unwrap_or(ESCAPE_SET)
+    }
+
+    /// Returns the address to send the email from.
+    #[inline]
+    #[must_use]
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    /// Returns whether the email should be flagged for follow-up.
+    #[inline]
+    #[must_use]
+    pub const fn followup_flag(&self) -> bool {
+        self.followup
+    }
+
+    /// Returns the importance of the email.
+    #[inline]
+    #[must_use]
+    pub const fn importance(&self) -> Importance {
+        self.importance
+    }
+
+    /// Returns the subject with the prefix prepended.
+    fn full_subject(&self) -> Cow<'_, str> {
+        if self.subj_prefix.is_empty() {
+            Cow::Borrowed(&self.subj)
+        } else {
+            Cow::Owned(format!("{}{}", self.subj_prefix, self.subj))
+        }
+    }
+
+    /// Returns the body with the signature appended.
+    fn full_body(&self) -> String {
+        match (self.body.is_empty(), self.signature.is_empty()) {
+            (_, true) => self.body.clone().into_owned(),
+            (true, false) => self.signature.clone(),
+            (false, false) => format!("{}\n\n{}", self.body, self.signature),
+        }
+    }
+
+    fn executable(&self) -> Result<&OsStr, SpawnError> {
+        match &self.exe {
+            Some(exe) => Ok(exe.as_os_str()),
+            None => outlook_path().map(OsStr::new).ok_or_else(|| {
+                outlook_not_found();
+                SpawnError::OutlookNotFound
+            }),
+        }
+    }
+
+    /// Builds the argument passed to Outlook's `/m` switch.
+    fn mailto_arg(&self) -> String {
+        let sep = self.separator.unwrap_or(';');
+        let set = self.escape_set();
+        let mut s = join_recipients(&self.to, sep, set);
+        push_escaped_param(&mut s, "cc", &join_recipients(&self.cc, sep, set));
+        push_escaped_param(&mut s, "bcc", &join_recipients(&self.bcc, sep, set));
+        push_escaped_param(&mut s, "subject", &escape_with(&self.full_subject(), set));
+        push_escaped_param(&mut s, "body", &self.rendered_body());
+        s
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        let s = self.mailto_arg();
+        let mut a = Vec::new();
+        if !self.profile.is_empty() {
+            a.push("/profile".to_owned());
+            a.push(self.profile.clone());
+        }
+        a.push("/c".to_owned());
+        if self.class.is_empty() {
+            a.push("ipm.note".to_owned());
+        } else {
+            a.push(self.class.clone());
+        }
+        a.push("/m".to_owned());
+        a.push(s);
+        for file in &self.files {
+            a.push("/a".to_owned());
+            a.push(file.clone());
+        }
+        if self.recycle {
+            a.push("/recycle".to_owned());
+        }
+        if self.nopreview {
+            a.push("/nopreview".to_owned());
+        }
+        if self.embedding {
+            a.push("/embedding".to_owned());
+        }
+        a.extend(self.raw.iter().cloned());
+        a
+    }
+
+    /// Returns the body as it is passed to Outlook in the `body=`
+    /// parameter of the `/m` switch, with the signature appended, and
+    /// escaped with its line breaks converted to `%0D%0A`, see
+    /// `with_escape_set()`.
+    #[must_use]
+    pub fn rendered_body(&self) -> String {
+        escape_with(&self.full_body(), self.escape_set())
+    }
+
+    /// Returns the command line that `spawn()` would run, without
+    /// spawning a process.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot be located
+    /// and no executable was set with `with_executable()`.
+    pub fn dry_run(&self) -> io::Result<String> {
+        Ok(command_line(
+            &self.executable()?.to_string_lossy(),
+            &self.build_command(),
+        ))
+    }
+
+    /// Returns a PowerShell command that spawns Outlook like `spawn()`,
+    /// e.g. `& 'OUTLOOK.EXE' @('/c', 'ipm.note', '/m', '?subject=Hi')`.
+    ///
+    /// Each argument is a single-quoted string, so backticks, `$`, and
+    /// double quotes are taken literally. PowerShell before 7.3 does not
+    /// escape double quotes when it passes arguments to a program, but
+    /// the `/m` argument never contains any, as they are percent-escaped.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot be located
+    /// and no executable was set with `with_executable()`.
+    pub fn to_powershell(&self) -> io::Result<String> {
+        let args: Vec<_> = self
+            .build_command()
+            .iter()
+            .map(|arg| powershell_quote(arg))
+            .collect();
+        Ok(format!(
+            "& {} @({})",
+            powershell_quote(&self.executable()?.to_string_lossy()),
+            args.join(", ")
+        ))
+    }
+
+    /// Returns a command line that spawns Outlook like `spawn()`, escaped
+    /// for use in a batch file.
+    ///
+    /// Every `%` is doubled, which a batch file reads back as a single
+    /// `%`, so this is not suitable for typing at an interactive prompt.
+    /// Characters that CMD treats specially, such as `&` and `^`, are
+    /// escaped with `^` unless they are within double quotes. Delayed
+    /// expansion of `!` is assumed to be disabled, as it is by default.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot be located
+    /// and no executable was set with `with_executable()`.
+    pub fn to_cmd(&self) -> io::Result<String> {
+        self.dry_run().map(|line| cmd_escape(&line))
+    }
+
+    /// Returns the `mailto:` URI that `spawn()` passes to Outlook's `/m`
+    /// switch, for previewing the email without building the whole
+    /// command.
+    ///
+    /// This uses Outlook's escaping rather than RFC 3986, see
+    /// `to_mailto()` for a standard URI.
+    #[must_use]
+    pub fn preview_mailto(&self) -> String {
+        format!("mailto:{}", self.mailto_arg())
+    }
+
+    /// Returns a standard `mailto:` URI for the email.
+    ///
+    /// Unlike the argument passed to Outlook's `/m` switch, this is
+    /// percent-encoded per RFC 3986, and recipients are separated by
+    /// commas. Attachments cannot be expressed in a `mailto:` URI, so
+    /// they are omitted. A high or low importance is carried by an
+    /// `X-Priority` header of 1 or 5, and omitted when normal.
+    #[must_use]
+    pub fn to_mailto(&self) -> String {
+        let mut s = String::from("mailto:");
+        s.push_str(&encode_addresses(&self.to));
+        let params = [
+            ("cc", encode_addresses(&self.cc)),
+            ("bcc", encode_addresses(&self.bcc)),
+            ("subject", uri_encode(&self.full_subject())),
+            ("body", uri_encode(&self.full_body())),
+            (
+                "X-Priority",
+                match self.importance {
+                    Importance::High => "1".to_owned(),
+                    Importance::Normal => String::new(),
+                    Importance::Low => "5".to_owned(),
+                },
+            ),
+        ];
+        let mut sep = '?';
+        for (key, value) in &params {
+            if !value.is_empty() {
+                s.push(sep);
+                s.push_str(key);
+                s.push('=');
+                s.push_str(value);
+                sep = '&';
+            }
+        }
+        s
+    }
+
+    /// Spawns an Outlook process, and prompts the user to press "Send".
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot be located
+    /// and no executable was set with `with_executable()`, if the command
+    /// line exceeds `MAX_COMMAND_LEN`, or if a child process cannot be
+    /// spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        self.try_spawn().map_err(io::Error::from)
+    }
+
+    /// Spawns an Outlook process, and returns its process ID.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`.
+    pub fn spawn_pid(self) -> io::Result<u32> {
+        self.spawn().map(|child| child.id())
+    }
+
+    /// Spawns an Outlook process, and prompts the user to press "Send".
+    ///
+    /// This is like `spawn()`, but distinguishes a missing Outlook
+    /// install from a failure to launch it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(SpawnError::OutlookNotFound)` if OUTLOOK.EXE
+    /// cannot be located and no executable was set with
+    /// `with_executable()`, `Err(SpawnError::CommandTooLong)` if the
+    /// command line exceeds `MAX_COMMAND_LEN`, or `Err(SpawnError::Spawn)`
+    /// if a child process cannot be spawned.
+    pub fn try_spawn(self) -> Result<process::Child, SpawnError> {
+        self.spawn_command_line().map(|(child, _)| child)
+    }
+
+    /// Spawns an Outlook process like `spawn()`, retrying up to
+    /// `attempts` times in total if the process cannot be spawned, e.g.
+    /// because Outlook is still starting up.
+    ///
+    /// Retries pass `/recycle`, and wait a little longer before each
+    /// attempt, starting at 100 ms. An `attempts` of 0 is treated as 1.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`, returning the error of the last
+    /// attempt. Only spawn failures are retried: if OUTLOOK.EXE cannot be
+    /// located or the command line is too long, this fails immediately.
+    pub fn spawn_with_retry(self, attempts: u32) -> io::Result<process::Child> {
+        self.retry(attempts, Self::try_spawn, std::thread::sleep)
+            .map_err(io::Error::from)
+    }
+
+    /// Calls `spawn` with the email, retrying like `spawn_with_retry()`,
+    /// and calling `sleep` with the backoff before each retry.
+    fn retry<T, S, W>(self, attempts: u32, mut spawn: S, mut sleep: W) -> Result<T, SpawnError>
+    where
+        S: FnMut(Self) -> Result<T, SpawnError>,
+        W: FnMut(Duration),
+    {
+        let mut mb = self;
+        let mut attempt = 1;
+        loop {
+            match spawn(mb.clone()) {
+                Err(SpawnError::Spawn(_)) if attempt < attempts => {
+                    sleep(Duration::from_millis(100 * u64::from(attempt)));
+                    mb = mb.recycle(true);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Spawns an Outlook process, and returns it along with the command
+    /// line that was used, e.g. for audit logging.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`.
+    pub fn spawn_logged(self) -> io::Result<(process::Child, String)> {
+        self.spawn_command_line().map_err(io::Error::from)
+    }
+
+    fn command(&self) -> Result<(process::Command, String), SpawnError> {
+        let exe = self.executable()?;
+        let args = self.build_command();
+        let line = command_line(&exe.to_string_lossy(), &args);
+        let len = line.encode_utf16().count();
+        if len > MAX_COMMAND_LEN {
+            return Err(SpawnError::CommandTooLong { len });
+        }
+        let mut cmd = process::Command::new(exe);
+        cmd.args(args);
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        if self.env_clear {
+            cmd.env_clear();
+        }
+        cmd.envs(self.env.iter().map(|(key, val)| (key, val)));
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+
+            const DETACHED_PROCESS: u32 = 0x0000_0008;
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+            if self.background {
+                cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+            }
+        }
+        Ok((cmd, line))
+    }
+
+    fn spawn_command_line(self) -> Result<(process::Child, String), SpawnError> {
+        let (mut cmd, line) = self.command()?;
+        let child = cmd.spawn().map_err(SpawnError::Spawn)?;
+        Ok((child, line))
+    }
+
+    /// Spawns an Outlook process with the email, to be saved as a draft.
+    ///
+    /// Outlook's invocation switches cannot save a draft unattended, so
+    /// this opens the same compose window as `spawn()`. The email is only
+    /// saved to the Drafts folder once the user saves or closes it.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`.
+    #[inline]
+    pub fn save_draft(self) -> io::Result<process::Child> {
+        self.spawn()
+    }
+
+    /// Checks that every recipient is a plausibly-formed email address.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(ValidationError::InvalidAddress)` for the first
+    /// recipient that is missing an `@`, a local part, or a domain.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.address_errors().next().map_or(Ok(()), Err)
+    }
+
+    /// Returns a `ValidationError::InvalidAddress` for every recipient
+    /// that `validate()` would reject.
+    fn address_errors(&self) -> impl Iterator<Item = ValidationError> + '_ {
+        let fields = [
+            (RecipientField::To, &self.to),
+            (RecipientField::Cc, &self.cc),
+            (RecipientField::Bcc, &self.bcc),
+        ];
+        IntoIterator::into_iter(fields).flat_map(|(field, recipients)| {
+            recipients
+                .iter()
+                .filter(|r| !is_plausible_address(r))
+                .map(move |recipient| ValidationError::InvalidAddress {
+                    field,
+                    recipient: recipient.clone(),
+                })
+        })
+    }
+
+    /// Returns `Err(ValidationError::NoRecipients)` if the email has no
+    /// recipients, CC, or BCC.
+    fn check_has_recipients(&self) -> Result<(), ValidationError> {
+        if self.to.is_empty() && self.cc.is_empty() && self.bcc.is_empty() {
+            return Err(ValidationError::NoRecipients);
+        }
+        Ok(())
+    }
+
+    /// Checks the email for every problem that would stop it from being
+    /// spawned, so that they can all be reported at once.
+    ///
+    /// A subject or body provided more than once is kept as the last one
+    /// provided, but is still reported here. Use `try_with_subject()` and
+    /// `try_with_body()` to catch those as they happen instead.
+    ///
+    /// # Errors
+    ///
+    /// Will return every `BuilderError` found, in the order: a subject
+    /// or body provided more than once, missing recipients and invalid
+    /// addresses, as `BuilderError::Validation`, then a missing
+    /// OUTLOOK.EXE or an oversized command line, as `BuilderError::Spawn`.
+    pub fn build(self) -> Result<ReadyMessage, Vec<BuilderError>> {
+        let mut errors = Vec::new();
+        if self.subj_repeated {
+            errors.push(BuilderError::SubjectAlreadySet);
+        }
+        if self.body_repeated {
+            errors.push(BuilderError::BodyAlreadySet);
+        }
+        errors.extend(
+            self.check_has_recipients()
+                .err()
+                .into_iter()
+                .chain(self.address_errors())
+                .map(BuilderError::from),
+        );
+        if let Err(e) = self.command() {
+            errors.push(e.into());
+        }
+        if errors.is_empty() {
+            Ok(ReadyMessage { message: self })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validates the email, then spawns an Outlook process.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` with `io::ErrorKind::InvalidInput`
+    /// wrapping a `ValidationError` if `validate()` fails, and otherwise
+    /// fails the same way as `spawn()`.
+    pub fn spawn_validated(self) -> io::Result<process::Child> {
+        self.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.spawn()
+    }
+
+    /// Resolves an attachment path against the working directory set with
+    /// `with_current_dir()`, if any.
+    fn attachment_path(&self, file: &str) -> PathBuf {
+        match &self.dir {
+            Some(dir) => dir.join(file),
+            None => PathBuf::from(file),
+        }
+    }
+
+    /// Returns the total size of the attachments, in bytes.
+    ///
+    /// Relative attachment paths are resolved against the directory set
+    /// with `with_current_dir()`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if an attachment cannot be found or
+    /// its metadata cannot be read.
+    pub fn attachment_size(&self) -> io::Result<u64> {
+        let mut total = 0;
+        for file in &self.files {
+            total += fs::metadata(self.attachment_path(file))?.len();
+        }
+        Ok(total)
+    }
+
+    /// Checks that the total size of the attachments is at most `max`
+    /// bytes, such as `MAX_ATTACHMENT_SIZE`, returning the total.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` with `io::ErrorKind::InvalidInput` if
+    /// the attachments exceed `max`, and otherwise fails the same way as
+    /// `attachment_size()`.
+    pub fn check_attachment_size(&self, max: u64) -> io::Result<u64> {
+        let total = self.attachment_size()?;
+        if total > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "attachments are {} bytes, exceeding the limit of {}",
+                    total, max
+                ),
+            ));
+        }
+        Ok(total)
+    }
+
+    /// Validates the email like `validate()`, and also checks that it has
+    /// at least one recipient, CC, or BCC, and that every attachment
+    /// exists.
+    ///
+    /// Relative attachment paths are resolved against the directory set
+    /// with `with_current_dir()`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(ValidationError::NoRecipients)` if the email has
+    /// no recipients, `Err(ValidationError::InvalidAddress)` if `validate()`
+    /// fails, or `Err(ValidationError::AttachmentMissing)` for the first
+    /// attachment that does not exist.
+    pub fn validate_strict(&self) -> Result<(), ValidationError> {
+        self.check_has_recipients()?;
+        self.validate()?;
+        for file in &self.files {
+            if !self.attachment_path(file).exists() {
+                return Err(ValidationError::AttachmentMissing(file.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the email with `validate_strict()`, then spawns an
+    /// Outlook process.
+    ///
+    /// Use `spawn()` instead to open an intentionally blank draft, or to
+    /// attach files that do not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` with `io::ErrorKind::InvalidInput`
+    /// wrapping a `ValidationError` if `validate_strict()` fails, and
+    /// otherwise fails the same way as `spawn()`.
+    pub fn spawn_strict(self) -> io::Result<process::Child> {
+        self.validate_strict()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.spawn()
+    }
+
+    /// Sends the email without user interaction, through Outlook's COM
+    /// automation interface.
+    ///
+    /// This creates an `Outlook.Application` object, builds a `MailItem`
+    /// with the recipients, subject, body, importance, sender, and
+    /// attachments, sends it from the account set with `with_account()`,
+    /// if any, and calls its `Send()` method. Options that only apply to
+    /// OUTLOOK.EXE, such as the profile, are ignored. Relative attachment
+    /// paths are resolved here, as they would be by `spawn()`.
+    ///
+    /// This requires the `com` feature.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(ComError::Unsupported)` on platforms other than
+    /// Windows, or for an RTF body, and `Err(ComError::Call)` if a COM
+    /// call fails, for example because Outlook is not installed, or its
+    /// security settings block programmatic sending.
+    #[cfg(feature = "com")]
+    pub fn send_via_com(self) -> Result<(), ComError> {
+        #[cfg(windows)]
+        {
+            let properties = self.com_properties()?;
+            let importance = match self.importance {
+                Importance::Low => 0,
+                Importance::Normal => 1,
+                Importance::High => 2,
+            };
+            let files = self
+                .files
+                .iter()
+                .map(|file| {
+                    let path = self.attachment_path(file);
+                    match std::env::current_dir() {
+                        Ok(dir) if path.is_relative() => dir.join(path),
+                        _ => path,
+                    }
+                })
+                .collect::<Vec<_>>();
+            com::send(&properties, importance, &self.account, &files)
+        }
+        #[cfg(not(windows))]
+        {
+            Err(ComError::Unsupported("platforms other than Windows"))
+        }
+    }
+
+    /// Returns the `MailItem` text properties that `send_via_com()` sets,
+    /// skipping empty ones.
+    #[cfg(all(feature = "com", any(windows, test)))]
+    fn com_properties(&self) -> Result<Vec<(&'static str, String)>, ComError> {
+        let body = match self.format {
+            BodyFormat::Plain => "Body",
+            BodyFormat::Html => "HTMLBody",
+            BodyFormat::Rtf => return Err(ComError::Unsupported("RTF bodies")),
+        };
+        let properties = vec![
+            ("To", self.to.join("; ")),
+            ("CC", self.cc.join("; ")),
+            ("BCC", self.bcc.join("; ")),
+            ("Subject", self.full_subject().into_owned()),
+            (body, self.full_body()),
+            ("SentOnBehalfOfName", self.from.clone()),
+        ];
+        Ok(properties
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect())
+    }
+
+    /// Spawns an Outlook process using `tokio::process`, and prompts the
+    /// user to press "Send".
+    ///
+    /// This requires the `tokio` feature.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_async(self) -> io::Result<tokio::process::Child> {
+        let (cmd, _) = self.command()?;
+        tokio::process::Command::from(cmd).spawn()
+    }
+
+    /// Spawns an Outlook process, and waits for it to exit.
+    ///
+    /// When Outlook is already running, the new process may hand the
+    /// email over to the existing instance and exit immediately, so the
+    /// exit status does not indicate whether the email was sent.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot be located
+    /// and no executable was set with `with_executable()`, or if a child
+    /// process cannot be spawned or waited on.
+    pub fn spawn_and_wait(self) -> io::Result<process::ExitStatus> {
+        self.spawn()?.wait()
+    }
+
+    /// Spawns an Outlook process, and returns a guard that waits for it
+    /// to exit when dropped.
+    ///
+    /// When Outlook is already running, or with `recycle(true)`, the new
+    /// process may hand the email over to the existing instance and exit
+    /// immediately, so the guard may stop waiting before the compose
+    /// window is closed.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as `spawn()`.
+    pub fn spawn_guarded(self) -> io::Result<SpawnGuard> {
+        self.spawn().map(|child| SpawnGuard { child })
+    }
+}
+
+impl<S> FromIterator<S> for MessageBuilder
+where
+    S: Into<String>,
+{
+    /// Creates a new `MessageBuilder` with each item as a recipient.
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+    {
+        Self::new().with_recipients(iter)
+    }
+}
+
+impl<S> Extend<S> for MessageBuilder
+where
+    S: Into<String>,
+{
+    /// Adds each item as a recipient.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = S>,
+    {
+        self.to.extend(iter.into_iter().map(Into::into));
+    }
+}
+
+impl fmt::Display for MessageBuilder {
+    /// Formats a human-readable summary of the email, e.g.
+    /// `To: a@example.org; Subject: Hello (1 attachment)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.to.is_empty() {
+            f.write_str("To: (no recipients)")?;
+        } else {
+            write!(f, "To: {}", self.to.join(", "))?;
+        }
+        if !self.cc.is_empty() {
+            write!(f, "; CC: {}", self.cc.join(", "))?;
+        }
+        if !self.bcc.is_empty() {
+            write!(f, "; BCC: {}", self.bcc.join(", "))?;
+        }
+        let subj = self.full_subject();
+        if subj.is_empty() {
+            f.write_str("; Subject: (no subject)")?;
+        } else {
+            write!(f, "; Subject: {}", subj)?;
+        }
+        match self.files.len() {
+            0 => Ok(()),
+            1 => f.write_str(" (1 attachment)"),
+            n => write!(f, " ({} attachments)", n),
+        }
+    }
+}
+
+/// A spawned Outlook process that is waited on when the guard is
+/// dropped, see `MessageBuilder::spawn_guarded()`.
+#[derive(Debug)]
+pub struct SpawnGuard {
+    child: process::Child,
+}
+
+impl SpawnGuard {
+    /// Returns the process ID of the Outlook process.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Waits for the Outlook process to exit, and returns its exit status.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if the process cannot be waited on.
+    pub fn wait(mut self) -> io::Result<process::ExitStatus> {
+        self.child.wait()
+    }
+}
+
+impl Drop for SpawnGuard {
+    fn drop(&mut self) {
+        // Errors cannot be reported from `drop`, so they are ignored.
+        let _ = self.child.wait();
+    }
+}
+
+/// An email that has passed `MessageBuilder::build()`, and is ready
+/// to be spawned.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReadyMessage {
+    message: MessageBuilder,
+}
+
+impl ReadyMessage {
+    /// Returns the email that was built.
+    #[inline]
+    #[must_use]
+    pub const fn message(&self) -> &MessageBuilder {
+        &self.message
+    }
+
+    /// Spawns an Outlook process, and prompts the user to press "Send".
+    ///
+    /// Every problem with the email itself was reported by `build()`, and
+    /// the location of OUTLOOK.EXE is only looked up once per process, so
+    /// this can only fail to spawn the process.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        match self.message.try_spawn() {
+            Ok(child) => Ok(child),
+            Err(SpawnError::Spawn(e)) => Err(e),
+            Err(e) => unreachable!("{} after MessageBuilder::build()", e),
+        }
+    }
+}
+
+/// The `AppointmentBuilder` type, for drafting Outlook calendar appointments.
+///
+/// Outlook's command-line switches can only prefill the subject, body,
+/// and attendees of an appointment. The location, start, and end are
+/// recorded on the builder, but OUTLOOK.EXE has no switch to carry them,
+/// so they are not passed along and must be filled in by the user.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AppointmentBuilder {
+    subj: String,
+    location: String,
+    start: String,
+    end: String,
+    body: String,
+    attendees: Vec<String>,
+    optional: Vec<String>,
+    reminder: Option<Duration>,
+    separator: Option<char>,
+    escape_set: Option<Vec<char>>,
+}
+
+impl AppointmentBuilder {
+    /// Creates a new `AppointmentBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            subj: String::new(),
+            location: String::new(),
+            start: String::new(),
+            end: String::new(),
+            body: String::new(),
+            attendees: Vec::new(),
+            optional: Vec::new(),
+            reminder: None,
+            separator: None,
+            escape_set: None,
+        }
+    }
+
+    /// Adds a subject to the appointment.
+    ///
+    /// This should only be called once per `AppointmentBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_subject<S>(self, subj: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(self.subj.is_empty(), "Outlook subject already provided");
+        Self {
+            subj: subj.into(),
+            location: self.location,
+            start: self.start,
+            end: self.end,
+            body: self.body,
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: self.reminder,
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Adds a location to the appointment.
+    ///
+    /// Outlook's invocation switches cannot set the location, so it is
+    /// not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_location<S>(self, location: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            location: location.into(),
+            start: self.start,
+            end: self.end,
+            body: self.body,
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: self.reminder,
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Adds a start time to the appointment, e.g. `"2020-01-31 09:00"`.
+    ///
+    /// Outlook's invocation switches cannot set the start time, so it is
+    /// not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_start<S>(self, start: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            location: self.location,
+            start: start.into(),
+            end: self.end,
+            body: self.body,
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: self.reminder,
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Adds an end time to the appointment, e.g. `"2020-01-31 09:30"`.
+    ///
+    /// Outlook's invocation switches cannot set the end time, so it is
+    /// not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_end<S>(self, end: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            location: self.location,
+            start: self.start,
+            end: end.into(),
+            body: self.body,
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: self.reminder,
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Adds a body to the appointment.
+    ///
+    /// This should only be called once per `AppointmentBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body<S>(self, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(self.body.is_empty(), "Outlook body already provided");
+        Self {
+            subj: self.subj,
+            location: self.location,
+            start: self.start,
+            end: self.end,
+            body: body.into(),
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: self.reminder,
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Adds a required attendee to the appointment, making it a meeting
+    /// request.
+    ///
+    /// Attendees are passed to Outlook's `/m` switch the way recipients
+    /// of an email are, joined with the separator set with
+    /// `with_recipient_separator()`.
+    #[inline]
+    #[must_use]
+    pub fn with_attendee<S>(mut self, attendee: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.attendees.push(attendee.into());
+        self
+    }
+
+    /// Adds an optional attendee to the appointment, making it a meeting
+    /// request.
+    ///
+    /// Optional attendees are passed to Outlook's `/m` switch the way CC
+    /// recipients of an email are, joined with the separator set with
+    /// `with_recipient_separator()`.
+    #[inline]
+    #[must_use]
+    pub fn with_optional_attendee<S>(mut self, attendee: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.optional.push(attendee.into());
+        self
+    }
+
+    /// Sets the separator used to join multiple attendees, which is `';'`
+    /// by default, see `MessageBuilder::with_recipient_separator()`.
+    #[inline]
+    #[must_use]
+    pub const fn with_recipient_separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Sets the characters, besides `%`, that are percent-encoded in
+    /// the `/m` argument, replacing `ESCAPE_SET`, see
+    /// `MessageBuilder::with_escape_set()`.
+    #[inline]
+    #[must_use]
+    pub fn with_escape_set(mut self, chars: &[char]) -> Self {
+        self.escape_set = Some(chars.to_vec());
+        self
+    }
+
+    /// Returns the characters, besides `%`, that are percent-encoded in
+    /// the `/m` argument, see `with_escape_set()`.
+    #[inline]
+    #[must_use]
+    pub fn escape_set(&self) -> &[char] {
+        self.escape_set.as_deref().unwrap_or(ESCAPE_SET)
+    }
+
+    /// Sets how long before the start of the appointment Outlook should
+    /// remind the user.
+    ///
+    /// Outlook's invocation switches cannot set a reminder, so it is not
+    /// passed to OUTLOOK.EXE, and Outlook's default reminder applies.
+    #[inline]
+    #[must_use]
+    pub fn with_reminder(self, reminder: Duration) -> Self {
+        Self {
+            subj: self.subj,
+            location: self.location,
+            start: self.start,
+            end: self.end,
+            body: self.body,
+            attendees: self.attendees,
+            optional: self.optional,
+            reminder: Some(reminder),
+            separator: self.separator,
+            escape_set: self.escape_set,
+        }
+    }
+
+    /// Returns how long before the start of the appointment Outlook
+    /// should remind the user, if set.
+    #[inline]
+    #[must_use]
+    pub const fn reminder(&self) -> Option<Duration> {
+        self.reminder
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        let sep = self.separator.unwrap_or(';');
+        let set = self.escape_set();
+        let mut s = join_recipients(&self.attendees, sep, set);
+        push_escaped_param(&mut s, "cc", &join_recipients(&self.optional, sep, set));
+        push_escaped_param(&mut s, "subject", &escape_with(&self.subj, set));
+        push_escaped_param(&mut s, "body", &escape_with(&self.body, set));
+        let mut a = vec!["/c".to_owned(), "ipm.appointment".to_owned()];
+        if !s.is_empty() {
+            a.push("/m".to_owned());
+            a.push(s);
+        }
+        a
+    }
+
+    /// Spawns an Outlook process with a new appointment.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+    /// be located, or if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        process::Command::new(outlook_exe()?)
+            .args(self.build_command())
+            .spawn()
+    }
+}
+
+/// The `ContactBuilder` type, for drafting Outlook contacts.
+///
+/// Outlook's command-line switches can only carry an email address,
+/// which is passed along on a best-effort basis. The full name, company,
+/// and phone number are recorded on the builder, but OUTLOOK.EXE has no
+/// switch to carry them, so they must be filled in by the user.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContactBuilder {
+    name: String,
+    email: String,
+    company: String,
+    phone: String,
+}
+
+impl ContactBuilder {
+    /// Creates a new `ContactBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            name: String::new(),
+            email: String::new(),
+            company: String::new(),
+            phone: String::new(),
+        }
+    }
+
+    /// Adds a full name to the contact.
+    ///
+    /// Outlook's invocation switches cannot set the full name, so it is
+    /// not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_full_name<S>(self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            email: self.email,
+            company: self.company,
+            phone: self.phone,
+        }
+    }
+
+    /// Adds an email address to the contact.
+    #[inline]
+    #[must_use]
+    pub fn with_email<S>(self, email: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: self.name,
+            email: email.into(),
+            company: self.company,
+            phone: self.phone,
+        }
+    }
+
+    /// Adds a company to the contact.
+    ///
+    /// Outlook's invocation switches cannot set the company, so it is
+    /// not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_company<S>(self, company: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: self.name,
+            email: self.email,
+            company: company.into(),
+            phone: self.phone,
+        }
+    }
+
+    /// Adds a phone number to the contact.
+    ///
+    /// Outlook's invocation switches cannot set the phone number, so it
+    /// is not passed to OUTLOOK.EXE.
+    #[inline]
+    #[must_use]
+    pub fn with_phone<S>(self, phone: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: self.name,
+            email: self.email,
+            company: self.company,
+            phone: phone.into(),
+        }
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        let mut a = vec!["/c".to_owned(), "ipm.contact".to_owned()];
+        if !self.email.is_empty() {
+            a.push("/m".to_owned());
+            a.push(escape(&self.email));
+        }
+        a
+    }
+
+    /// Spawns an Outlook process with a new contact.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+    /// be located, or if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        process::Command::new(outlook_exe()?)
+            .args(self.build_command())
+            .spawn()
+    }
+}
+
+/// The `TaskBuilder` type, for drafting Outlook tasks.
+///
+/// Outlook's command-line switches can only prefill the subject and body
+/// of a task. The due date and priority are recorded on the builder, but
+/// OUTLOOK.EXE has no switch to carry them, so they may be ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TaskBuilder {
+    subj: String,
+    body: String,
+    due: String,
+    priority: Importance,
+    reminder: Option<Duration>,
+}
+
+impl TaskBuilder {
+    /// Creates a new `TaskBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            subj: String::new(),
+            body: String::new(),
+            due: String::new(),
+            priority: Importance::Normal,
+            reminder: None,
+        }
+    }
+
+    /// Adds a subject to the task.
+    ///
+    /// This should only be called once per `TaskBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_subject<S>(self, subj: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(self.subj.is_empty(), "Outlook subject already provided");
+        Self {
+            subj: subj.into(),
+            body: self.body,
+            due: self.due,
+            priority: self.priority,
+            reminder: self.reminder,
+        }
+    }
+
+    /// Adds a body to the task.
+    ///
+    /// This should only be called once per `TaskBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body<S>(self, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(self.body.is_empty(), "Outlook body already provided");
+        Self {
+            subj: self.subj,
+            body: body.into(),
+            due: self.due,
+            priority: self.priority,
+            reminder: self.reminder,
+        }
+    }
+
+    /// Adds a due date to the task, e.g. `"2020-01-31"`.
+    ///
+    /// Outlook's invocation switches cannot set the due date, so it may
+    /// be ignored.
+    #[inline]
+    #[must_use]
+    pub fn with_due_date<S>(self, due: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            subj: self.subj,
+            body: self.body,
+            due: due.into(),
+            priority: self.priority,
+            reminder: self.reminder,
+        }
+    }
+
+    /// Sets the priority of the task.
+    ///
+    /// Outlook's invocation switches cannot set the priority, so it may
+    /// be ignored.
+    #[inline]
+    #[must_use]
+    pub fn with_priority(self, priority: Importance) -> Self {
+        Self {
+            subj: self.subj,
+            body: self.body,
+            due: self.due,
+            priority,
+            reminder: self.reminder,
+        }
+    }
+
+    /// Sets how long before the due date of the task Outlook should
+    /// remind the user.
+    ///
+    /// Outlook's invocation switches cannot set a reminder, so it is not
+    /// passed to OUTLOOK.EXE, and Outlook's default reminder applies.
+    #[inline]
+    #[must_use]
+    pub fn with_reminder(self, reminder: Duration) -> Self {
+        Self {
+            subj: self.subj,
+            body: self.body,
+            due: self.due,
+            priority: self.priority,
+            reminder: Some(reminder),
+        }
+    }
+
+    /// Returns how long before the due date of the task Outlook should
+    /// remind the user, if set.
+    #[inline]
+    #[must_use]
+    pub const fn reminder(&self) -> Option<Duration> {
+        self.reminder
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        let mut s = String::new();
+        push_param(&mut s, "subject", &self.subj);
+        push_param(&mut s, "body", &self.body);
+        let mut a = vec!["/c".to_owned(), "ipm.task".to_owned()];
+        if !s.is_empty() {
+            a.push("/m".to_owned());
+            a.push(s);
+        }
+        a
+    }
+
+    /// Spawns an Outlook process with a new task.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+    /// be located, or if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        process::Command::new(outlook_exe()?)
+            .args(self.build_command())
+            .spawn()
+    }
+}
+
+/// The `StickyNoteBuilder` type, for drafting Outlook sticky notes.
+///
+/// Sticky notes have no recipients or subject, so only the body is
+/// meaningful.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StickyNoteBuilder {
+    body: String,
+}
+
+impl StickyNoteBuilder {
+    /// Creates a new `StickyNoteBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            body: String::new(),
+        }
+    }
+
+    /// Adds a body to the sticky note.
+    ///
+    /// This should only be called once per `StickyNoteBuilder` instance.
+    #[inline]
+    #[must_use]
+    pub fn with_body<S>(self, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        debug_assert!(self.body.is_empty(), "Outlook body already provided");
+        Self { body: body.into() }
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        let mut s = String::new();
+        push_param(&mut s, "body", &self.body);
+        let mut a = vec!["/c".to_owned(), "ipm.stickynote".to_owned()];
+        if !s.is_empty() {
+            a.push("/m".to_owned());
+            a.push(s);
+        }
+        a
+    }
+
+    /// Spawns an Outlook process with a new sticky note.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+    /// be located, or if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        process::Command::new(outlook_exe()?)
+            .args(self.build_command())
+            .spawn()
+    }
+}
+
+/// The `TemplateBuilder` type, for opening Outlook templates (`.oft` files).
+///
+/// Outlook does not document combining the `/t` switch with any other
+/// switch, so recipients and a subject cannot be layered onto the
+/// template, and must be filled in once it opens.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TemplateBuilder {
+    path: String,
+}
+
+impl TemplateBuilder {
+    /// Creates a new `TemplateBuilder` for the template at `path`.
+    ///
+    /// The path is passed to Outlook as a single argument, so it may
+    /// contain spaces.
+    #[inline]
+    #[must_use]
+    pub fn open<P>(path: P) -> Self
+    where
+        P: Into<String>,
+    {
+        Self { path: path.into() }
+    }
+
+    /// Builds the arguments that `spawn()` passes to OUTLOOK.EXE,
+    /// without spawning a process.
+    #[must_use]
+    pub fn build_command(&self) -> Vec<String> {
+        vec!["/t".to_owned(), self.path.clone()]
+    }
+
+    /// Spawns an Outlook process with a new item based on the template.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+    /// be located, or if a child process cannot be spawned.
+    pub fn spawn(self) -> io::Result<process::Child> {
+        process::Command::new(outlook_exe()?)
+            .args(self.build_command())
+            .spawn()
+    }
+}
+
+fn print_message_args(path: String) -> Vec<String> {
+    vec!["/p".to_owned(), path]
+}
+
+/// Spawns an Outlook process that prints a saved `.msg` file to the
+/// default printer.
+///
+/// The path is passed to Outlook as a single argument, so it may
+/// contain spaces.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+pub fn print_message<P>(path: P) -> io::Result<process::Child>
+where
+    P: Into<String>,
+{
+    process::Command::new(outlook_exe()?)
+        .args(print_message_args(path.into()))
+        .spawn()
+}
+
+fn open_message_args(path: String) -> Vec<String> {
+    vec!["/f".to_owned(), path]
+}
+
+/// Spawns an Outlook process that opens a saved `.msg` file.
+///
+/// The path is passed to Outlook as a single argument, so it may
+/// contain spaces.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+pub fn open_message<P>(path: P) -> io::Result<process::Child>
+where
+    P: Into<String>,
+{
+    process::Command::new(outlook_exe()?)
+        .args(open_message_args(path.into()))
+        .spawn()
+}
+
+fn select_args(folder: &str) -> Vec<String> {
+    vec!["/select".to_owned(), format!("outlook:{}", folder)]
+}
+
+fn select(folder: &str) -> io::Result<process::Child> {
+    process::Command::new(outlook_exe()?)
+        .args(select_args(folder))
+        .spawn()
+}
+
+/// Spawns an Outlook process that opens the calendar.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+#[inline]
+pub fn open_calendar() -> io::Result<process::Child> {
+    select("calendar")
+}
+
+/// Spawns an Outlook process that opens the inbox.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+#[inline]
+pub fn open_inbox() -> io::Result<process::Child> {
+    select("inbox")
+}
+
+/// Spawns an Outlook process that opens the contacts.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+#[inline]
+pub fn open_contacts() -> io::Result<process::Child> {
+    select("contacts")
+}
+
+fn safe_mode_args() -> Vec<String> {
+    vec!["/safe".to_owned()]
+}
+
+/// Spawns an Outlook process in safe mode, with add-ins disabled, for
+/// troubleshooting.
+///
+/// # Errors
+///
+/// Will return `Err(io::Error)` if OUTLOOK.EXE cannot
+/// be located, or if a child process cannot be spawned.
+pub fn spawn_safe_mode() -> io::Result<process::Child> {
+    process::Command::new(outlook_exe()?)
+        .args(safe_mode_args())
+        .spawn()
+}
+
+/// Late-bound calls into Outlook's COM automation interface, for
+/// `MessageBuilder::send_via_com()`.
+#[cfg(all(windows, feature = "com"))]
+mod com {
+    use super::ComError;
+    use std::{convert::TryFrom, mem::ManuallyDrop, path::PathBuf, ptr};
+    use windows::{
+        core::{w, BSTR, GUID, PCWSTR},
+        Win32::{
+            Foundation::{E_NOINTERFACE, RPC_E_CHANGED_MODE},
+            System::{
+                Com::{
+                    CLSIDFromProgID, CoCreateInstance, CoInitializeEx, CoUninitialize, IDispatch,
+                    CLSCTX_LOCAL_SERVER, COINIT_APARTMENTTHREADED, DISPATCH_FLAGS, DISPATCH_METHOD,
+                    DISPATCH_PROPERTYGET, DISPATCH_PROPERTYPUT, DISPATCH_PROPERTYPUTREF,
+                    DISPPARAMS,
+                },
+                Ole::DISPID_PROPERTYPUT,
+                Variant::{
+                    VariantClear, VARENUM, VARIANT, VARIANT_0, VARIANT_0_0, VARIANT_0_0_0, VT_BSTR,
+                    VT_DISPATCH, VT_I4,
+                },
+            },
+        },
+    };
+
+    /// `LOCALE_USER_DEFAULT`, for names and arguments.
+    const LOCALE_USER_DEFAULT: u32 = 0x0400;
+
+    /// `OlItemType::olMailItem`, for `Application.CreateItem()`.
+    const OL_MAIL_ITEM: i32 = 0;
+
+    /// Maps a failed COM call to a `ComError`.
+    fn call(name: &'static str) -> impl Fn(windows::core::Error) -> ComError {
+        move |e| ComError::Call {
+            name,
+            code: e.code().0,
+        }
+    }
+
+    /// Initializes COM on this thread for as long as it is alive.
+    struct Apartment {
+        initialized: bool,
+    }
+
+    impl Apartment {
+        fn enter() -> Result<Self, ComError> {
+            let hr = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+            if hr == RPC_E_CHANGED_MODE {
+                // COM was already initialized on this thread, differently
+                return Ok(Self { initialized: false });
+            }
+            hr.ok().map_err(call("CoInitializeEx"))?;
+            Ok(Self { initialized: true })
+        }
+    }
+
+    impl Drop for Apartment {
+        fn drop(&mut self) {
+            if self.initialized {
+                unsafe { CoUninitialize() };
+            }
+        }
+    }
+
+    /// An owned `VARIANT`, cleared when dropped.
+    #[repr(transparent)]
+    struct Variant(VARIANT);
+
+    impl Variant {
+        fn new(vt: VARENUM, value: VARIANT_0_0_0) -> Self {
+            Self(VARIANT {
+                Anonymous: VARIANT_0 {
+                    Anonymous: ManuallyDrop::new(VARIANT_0_0 {
+                        vt,
+                        wReserved1: 0,
+                        wReserved2: 0,
+                        wReserved3: 0,
+                        Anonymous: value,
+                    }),
+                },
+            })
+        }
+
+        fn int(n: i32) -> Self {
+            Self::new(VT_I4, VARIANT_0_0_0 { lVal: n })
+        }
+
+        fn string(s: &str) -> Self {
+            let bstr = ManuallyDrop::new(BSTR::from(s));
+            Self::new(VT_BSTR, VARIANT_0_0_0 { bstrVal: bstr })
+        }
+
+        fn object(obj: &IDispatch) -> Self {
+            let obj = ManuallyDrop::new(Some(obj.clone()));
+            Self::new(VT_DISPATCH, VARIANT_0_0_0 { pdispVal: obj })
+        }
+
+        /// Returns the object held by the `VARIANT` returned by `name`.
+        fn into_object(self, name: &'static str) -> Result<IDispatch, ComError> {
+            let obj = unsafe {
+                let v = &self.0.Anonymous.Anonymous;
+                if v.vt == VT_DISPATCH {
+                    (*v.Anonymous.pdispVal).clone()
+                } else {
+                    None
+                }
+            };
+            obj.ok_or(ComError::Call {
+                name,
+                code: E_NOINTERFACE.0,
+            })
+        }
+    }
+
+    impl Drop for Variant {
+        fn drop(&mut self) {
+            let _ = unsafe { VariantClear(&mut self.0) };
+        }
+    }
+
+    /// Calls the method or property `name` of `obj` with `args`.
+    fn invoke(
+        obj: &IDispatch,
+        name: &'static str,
+        flags: DISPATCH_FLAGS,
+        mut args: Vec<Variant>,
+    ) -> Result<Variant, ComError> {
+        let wide = name.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
+        let mut id = 0;
+        unsafe {
+            obj.GetIDsOfNames(
+                &GUID::zeroed(),
+                &PCWSTR(wide.as_ptr()),
+                1,
+                LOCALE_USER_DEFAULT,
+                &mut id,
+            )
+        }
+        .map_err(call(name))?;
+        args.reverse(); // arguments are passed last to first
+        let put = flags == DISPATCH_PROPERTYPUT || flags == DISPATCH_PROPERTYPUTREF;
+        let mut named = DISPID_PROPERTYPUT;
+        let params = DISPPARAMS {
+            rgvarg: args.as_mut_ptr().cast(),
+            rgdispidNamedArgs: if put { &mut named } else { ptr::null_mut() },
+            cArgs: u32::try_from(args.len()).unwrap_or(u32::MAX),
+            cNamedArgs: u32::from(put),
+        };
+        let mut result = Variant(VARIANT::default());
+        unsafe {
+            obj.Invoke(
+                id,
+                &GUID::zeroed(),
+                LOCALE_USER_DEFAULT,
+                flags,
+                &params,
+                Some(&mut result.0),
+                None,
+                None,
+            )
+        }
+        .map_err(call(name))?;
+        Ok(result)
+    }
+
+    /// Creates and sends a `MailItem`.
+    pub(super) fn send(
+        properties: &[(&'static str, String)],
+        importance: i32,
+        account: &str,
+        files: &[PathBuf],
+    ) -> Result<(), ComError> {
+        let _apartment = Apartment::enter()?;
+        let app: IDispatch = unsafe {
+            let clsid =
+                CLSIDFromProgID(w!("Outlook.Application")).map_err(call("CLSIDFromProgID"))?;
+            CoCreateInstance(&clsid, None, CLSCTX_LOCAL_SERVER).map_err(call("CoCreateInstance"))?
+        };
+        let item = invoke(
+            &app,
+            "CreateItem",
+            DISPATCH_METHOD,
+            vec![Variant::int(OL_MAIL_ITEM)],
+        )?
+        .into_object("CreateItem")?;
+        for (name, value) in properties {
+            invoke(
+                &item,
+                name,
+                DISPATCH_PROPERTYPUT,
+                vec![Variant::string(value)],
+            )?;
+        }
+        invoke(
+            &item,
+            "Importance",
+            DISPATCH_PROPERTYPUT,
+            vec![Variant::int(importance)],
+        )?;
+        if !account.is_empty() {
+            let session =
+                invoke(&app, "Session", DISPATCH_PROPERTYGET, vec![])?.into_object("Session")?;
+            let accounts = invoke(&session, "Accounts", DISPATCH_PROPERTYGET, vec![])?
+                .into_object("Accounts")?;
+            let account = invoke(
+                &accounts,
+                "Item",
+                DISPATCH_METHOD,
+                vec![Variant::string(account)],
+            )?
+            .into_object("Item")?;
+            invoke(
+                &item,
+                "SendUsingAccount",
+                DISPATCH_PROPERTYPUTREF,
+                vec![Variant::object(&account)],
+            )?;
+        }
+        if !files.is_empty() {
+            let attachments = invoke(&item, "Attachments", DISPATCH_PROPERTYGET, vec![])?
+                .into_object("Attachments")?;
+            for file in files {
+                let file = file.to_string_lossy();
+                invoke(
+                    &attachments,
+                    "Add",
+                    DISPATCH_METHOD,
+                    vec![Variant::string(&file)],
+                )?;
+            }
+        }
+        invoke(&item, "Send", DISPATCH_METHOD, vec![])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An executable that accepts arbitrary arguments and exits quickly.
+    #[cfg(windows)]
+    const STUB_EXE: &str = "cmd.exe";
+    #[cfg(not(windows))]
+    const STUB_EXE: &str = "true";
+
+    #[test]
+    fn message_builder() {
+        // A dumb test
+        let mb = MessageBuilder::new();
+        assert_eq!(mb.to.len(), 0);
+        assert_eq!(mb.cc.len(), 0);
+        assert_eq!(mb.bcc.len(), 0);
+        assert_eq!(mb.subj, "");
+        assert_eq!(mb.body, "");
+        assert_eq!(mb.files.len(), 0);
+        let mb = mb
+            .with_recipient("noreply@example.org")
+            .with_subject("Hello, World!")
+            .with_body("Line with spaces\nAnother line")
+            .with_attachment("C:/tmp/file.txt");
+        assert_eq!(mb.to.len(), 1);
+        assert_eq!(mb.cc.len(), 0);
+        assert_eq!(mb.bcc.len(), 0);
+        assert_eq!(mb.to[0], "noreply@example.org");
+        assert_eq!(mb.subj, "Hello, World!");
+        assert_eq!(mb.body, "Line with spaces\nAnother line");
+        assert_eq!(mb.files.len(), 1);
+        assert_eq!(mb.files[0], "C:/tmp/file.txt");
+    }
+
+    #[test]
+    fn appointment_builder() {
+        let ab = AppointmentBuilder::new()
+            .with_subject("Q&A?")
+            .with_location("Room 1")
+            .with_start("2020-01-31 09:00")
+            .with_end("2020-01-31 09:30")
+            .with_body("100% attendance");
+        assert_eq!(ab.location, "Room 1");
+        assert_eq!(ab.start, "2020-01-31 09:00");
+        assert_eq!(ab.end, "2020-01-31 09:30");
+        assert_eq!(
+            ab.build_command(),
+            [
+                "/c",
+                "ipm.appointment",
+                "/m",
+                "?subject=Q%26A%3F&body=100%25 attendance"
+            ]
+        );
+        assert_eq!(
+            AppointmentBuilder::new().build_command(),
+            ["/c", "ipm.appointment"]
+        );
+    }
+
+    #[test]
+    fn contact_builder() {
+        let cb = ContactBuilder::new()
+            .with_full_name("Jane Doe")
+            .with_email("jane@example.org")
+            .with_company("Example & Co")
+            .with_phone("555-0100");
+        assert_eq!(cb.name, "Jane Doe");
+        assert_eq!(cb.company, "Example & Co");
+        assert_eq!(cb.phone, "555-0100");
+        let args = cb.build_command();
+        assert_eq!(args[..2], ["/c", "ipm.contact"]);
+        assert_eq!(args[2..], ["/m", "jane@example.org"]);
+    }
+
+    #[test]
+    fn task_builder() {
+        let tb = TaskBuilder::new()
+            .with_subject("Review \"Q1\" & file")
+            .with_due_date("2020-01-31")
+            .with_priority(Importance::High);
+        assert_eq!(tb.due, "2020-01-31");
+        assert_eq!(tb.priority, Importance::High);
+        assert_eq!(
+            tb.build_command(),
+            ["/c", "ipm.task", "/m", "?subject=Review %22Q1%22 %26 file"]
+        );
+    }
+
+    #[test]
+    fn sticky_note_builder() {
+        let sb = StickyNoteBuilder::new().with_body("Buy milk & eggs");
+        assert_eq!(
+            sb.build_command(),
+            ["/c", "ipm.stickynote", "/m", "?body=Buy milk %26 eggs"]
+        );
+    }
+
+    #[test]
+    fn build_command() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient("b@example.org")
+            .with_recipient_cc("c@example.org")
+            .with_subject("Tom & Jerry")
+            .with_attachment("C:/tmp/file.txt");
+        assert_eq!(mb.build_command(), mb.build_command());
+        assert_eq!(
+            mb.build_command(),
+            [
+                "/c",
+                "ipm.note",
+                "/m",
+                "a@example.org;b@example.org?cc=c@example.org&subject=Tom %26 Jerry",
+                "/a",
+                "C:/tmp/file.txt",
+            ]
+        );
+        let mb = MessageBuilder::new()
+            .with_recipient_bcc("d@example.org")
+            .with_body("Hello");
+        assert_eq!(
+            mb.build_command(),
+            ["/c", "ipm.note", "/m", "?bcc=d@example.org&body=Hello"]
+        );
+    }
+
+    #[test]
+    fn quote_arg() {
+        assert_eq!(super::quote_arg("/c"), "/c");
+        assert_eq!(super::quote_arg(""), "\"\"");
+        assert_eq!(super::quote_arg("a b"), "\"a b\"");
+        assert_eq!(super::quote_arg("C:\\tmp\\"), "C:\\tmp\\");
+        assert_eq!(super::quote_arg("C:\\my dir\\"), "\"C:\\my dir\\\\\"");
+        assert_eq!(super::quote_arg("a\\\"b"), "a\\\\\\\"b");
+        assert_eq!(super::quote_arg("a\"b c\\"), "\"a\\\"b c\\\\\"");
+    }
+
+    #[test]
+    fn dry_run() {
+        let mb = MessageBuilder::new()
+            .with_subject("Hello & welcome")
+            .with_attachment("C:/tmp/file.txt");
+        let s = command_line("C:\\Outlook\\OUTLOOK.EXE", &mb.build_command());
+        assert_eq!(
+            s,
+            "C:\\Outlook\\OUTLOOK.EXE /c ipm.note /m \"?subject=Hello %26 welcome\" /a C:/tmp/file.txt"
+        );
+        assert!(s.contains("Hello %26 welcome"));
+        assert!(s.contains(" /a "));
+    }
+
+    #[test]
+    fn try_with_subject_and_body() {
+        let mb = MessageBuilder::new()
+            .try_with_subject("Hello")
+            .and_then(|mb| mb.try_with_body("World"))
+            .unwrap();
+        assert_eq!(mb.subj, "Hello");
+        assert_eq!(mb.body, "World");
+        assert_eq!(
+            mb.clone().try_with_subject("Again"),
+            Err(BuilderError::SubjectAlreadySet)
+        );
+        assert_eq!(mb.try_with_body("Again"), Err(BuilderError::BodyAlreadySet));
+    }
+
+    #[test]
+    fn multiple_attachments() {
+        let mb = MessageBuilder::new()
+            .with_attachment("C:/tmp/a.txt")
+            .with_attachment("C:/tmp/b.txt");
+        assert_eq!(
+            mb.build_command()[4..],
+            ["/a", "C:/tmp/a.txt", "/a", "C:/tmp/b.txt"]
+        );
+    }
+
+    #[test]
+    fn with_executable() {
+        let mb = MessageBuilder::new().with_subject("Hi");
+        assert_eq!(mb.exe, None);
+        let mb = mb.with_executable("C:\\Stub\\OUTLOOK.EXE");
+        assert_eq!(mb.executable().unwrap(), "C:\\Stub\\OUTLOOK.EXE");
+        assert_eq!(
+            mb.dry_run().unwrap(),
+            "C:\\Stub\\OUTLOOK.EXE /c ipm.note /m ?subject=Hi"
+        );
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn resolve_outlook_exe() {
+        let hklm = "C:\\Program Files\\Microsoft Office\\OUTLOOK.EXE";
+        let hkcu = "C:\\Users\\jane\\Office\\OUTLOOK.EXE";
+        let both = |hkey| match hkey {
+            h if h == HKEY_LOCAL_MACHINE => Some(hklm.to_owned()),
+            h if h == HKEY_CURRENT_USER => Some(hkcu.to_owned()),
+            _ => None,
+        };
+        let user = |hkey| Some(hkcu.to_owned()).filter(|_| hkey == HKEY_CURRENT_USER);
+        assert_eq!(super::resolve_outlook_exe(both).as_deref(), Some(hklm));
+        assert_eq!(super::resolve_outlook_exe(user).as_deref(), Some(hkcu));
+        assert_eq!(super::resolve_outlook_exe(|_| None), None);
+    }
+
+    #[test]
+    fn percent_escape_hash() {
+        assert_eq!(escape("#1 %23"), "%231 %2523");
+        let mb = MessageBuilder::new().with_body("See #42 and #43");
+        assert_eq!(mb.build_command()[3], "?body=See %2342 and %2343");
+    }
+
+    #[test]
+    fn percent_escape_newlines() {
+        assert_eq!(
+            escape("Line1\nLine2\r\nLine3\rLine4"),
+            "Line1%0D%0ALine2%0D%0ALine3%0D%0ALine4"
+        );
+    }
+
+    #[test]
+    fn from_mailto() {
+        let mb = MessageBuilder::from_mailto(
+            "mailto:a@example.org,%20b@example.org?CC=c@example.org;d@example.org\
+             &subject=Tom%20%26%20Jerry%3F&body=50%25%0D%0Aoff&x-foo=bar&bcc=",
+        )
+        .unwrap();
+        assert_eq!(mb.to, ["a@example.org", "b@example.org"]);
+        assert_eq!(mb.cc, ["c@example.org", "d@example.org"]);
+        assert_eq!(mb.bcc.len(), 0);
+        assert_eq!(mb.subj, "Tom & Jerry?");
+        assert_eq!(mb.body, "50%\r\noff");
+
+        let mb = MessageBuilder::from_mailto("MAILTO:a@example.org").unwrap();
+        assert_eq!(mb.to, ["a@example.org"]);
+        assert_eq!(
+            MessageBuilder::from_mailto("mailto:").unwrap(),
+            MessageBuilder::new()
+        );
+        assert_eq!(
+            MessageBuilder::from_mailto("a@example.org"),
+            Err(ParseError::MissingScheme)
+        );
+        assert_eq!(
+            MessageBuilder::from_mailto("mailtoé:x"),
+            Err(ParseError::MissingScheme)
+        );
+        assert_eq!(
+            MessageBuilder::from_mailto("mailto:?subject=100%"),
+            Err(ParseError::InvalidPercentEncoding)
+        );
+        assert_eq!(
+            MessageBuilder::from_mailto("mailto:?subject=%FF"),
+            Err(ParseError::InvalidUtf8)
+        );
+    }
+
+    #[test]
+    fn to_mailto() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient("b@example.org")
+            .with_recipient_bcc("c@example.org")
+            .with_subject("Tom & Jerry?")
+            .with_body("50% off\nnow");
+        assert_eq!(
+            mb.to_mailto(),
+            "mailto:a@example.org,b@example.org?bcc=c@example.org\
+             &subject=Tom%20%26%20Jerry%3F&body=50%25%20off%0D%0Anow"
+        );
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient_cc("b@example.org, c")
+            .with_subject("Tom & Jerry?")
+            .with_body("50% off\r\nnow");
+        assert_eq!(MessageBuilder::from_mailto(&mb.to_mailto()).unwrap(), mb);
+        assert_eq!(MessageBuilder::new().to_mailto(), "mailto:");
+    }
+
+    #[test]
+    fn with_recipients() {
+        let mb = MessageBuilder::new()
+            .with_recipients(
+                ["a@example.org", "b@example.org", "c@example.org"]
+                    .iter()
+                    .copied(),
+            )
+            .with_recipients_cc(vec!["d@example.org".to_owned()])
+            .with_recipients_bcc(Some("e@example.org"));
+        assert_eq!(mb.to.len(), 3);
+        assert_eq!(mb.to, ["a@example.org", "b@example.org", "c@example.org"]);
+        assert_eq!(mb.cc, ["d@example.org"]);
+        assert_eq!(mb.bcc, ["e@example.org"]);
+    }
+
+    #[test]
+    fn spawn_and_wait() {
+        let status = MessageBuilder::new()
+            .with_subject("Hello")
+            .with_executable(STUB_EXE)
+            .spawn_and_wait();
+        assert!(status.is_ok());
+    }
+
+    #[test]
+    fn validate() {
+        assert!(is_plausible_address("jane@example.org"));
+        assert!(is_plausible_address("Jane Doe <jane@example.org>"));
+        assert!(is_plausible_address("\"Doe, Jane\" <jane@example.org>"));
+        assert!(!is_plausible_address("jane"));
+        assert!(!is_plausible_address("@example.org"));
+        assert!(!is_plausible_address("jane@"));
+        assert!(!is_plausible_address("Jane Doe <jane>"));
+        assert!(!is_plausible_address("jane doe@example.org"));
+
+        let mb = MessageBuilder::new()
+            .with_recipient("Jane Doe <jane@example.org>")
+            .with_recipient_cc("john@example.org");
+        assert_eq!(mb.validate(), Ok(()));
+        let mb = mb.with_recipient_bcc("typo.example.org");
+        assert_eq!(
+            mb.validate(),
+            Err(ValidationError::InvalidAddress {
+                field: RecipientField::Bcc,
+                recipient: "typo.example.org".to_owned(),
+            })
+        );
+        let err = mb.with_executable(STUB_EXE).spawn_validated().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn with_named_recipient() {
+        let mb = MessageBuilder::new()
+            .with_named_recipient("Jane Doe", "jane@example.org")
+            .with_named_recipient("Jim \"JJ\" Doe", "jim@example.org");
+        assert_eq!(
+            mb.to,
+            [
+                "\"Jane Doe\" <jane@example.org>",
+                "\"Jim \\\"JJ\\\" Doe\" <jim@example.org>"
+            ]
+        );
+        assert_eq!(
+            mb.build_command()[3],
+            "%22Jane Doe%22 %3Cjane@example.org%3E;%22Jim \\%22JJ\\%22 Doe%22 %3Cjim@example.org%3E"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient_cc("b@example.org")
+            .with_recipient_bcc("c@example.org")
+            .with_subject("Hello, World!")
+            .with_body("Line with spaces\nAnother line")
+            .with_attachment("C:/tmp/file.txt")
+            .with_executable("C:/Stub/OUTLOOK.EXE");
+        let json = serde_json::to_string(&mb).unwrap();
+        assert_eq!(serde_json::from_str::<MessageBuilder>(&json).unwrap(), mb);
+    }
+
+    #[test]
+    fn getters() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient_cc("b@example.org")
+            .with_recipient_bcc("c@example.org")
+            .with_subject("Hello, World!")
+            .with_body("Line with spaces\nAnother line")
+            .with_attachment("C:/tmp/file.txt");
+        assert_eq!(mb.subject(), "Hello, World!");
+        assert_eq!(mb.recipients(), ["a@example.org"]);
+        assert_eq!(mb.cc(), ["b@example.org"]);
+        assert_eq!(mb.bcc(), ["c@example.org"]);
+        assert_eq!(mb.body(), "Line with spaces\nAnother line");
+        assert_eq!(mb.attachments(), ["C:/tmp/file.txt"]);
+    }
+
+    #[test]
+    fn template_builder() {
+        let tb = TemplateBuilder::open("C:/My Templates/Weekly Report.oft");
+        assert_eq!(
+            tb.build_command(),
+            ["/t", "C:/My Templates/Weekly Report.oft"]
+        );
+        assert_eq!(
+            command_line("OUTLOOK.EXE", &tb.build_command()),
+            "OUTLOOK.EXE /t \"C:/My Templates/Weekly Report.oft\""
+        );
+    }
+
+    #[test]
+    fn print_message() {
+        let args = print_message_args("C:/Saved Mail/Receipt.msg".to_owned());
+        assert_eq!(args, ["/p", "C:/Saved Mail/Receipt.msg"]);
+        assert_eq!(
+            command_line("OUTLOOK.EXE", &args),
+            "OUTLOOK.EXE /p \"C:/Saved Mail/Receipt.msg\""
+        );
+    }
+
+    #[test]
+    fn open_message() {
+        let args = open_message_args("C:/Saved Mail/Receipt.msg".to_owned());
+        assert_eq!(args, ["/f", "C:/Saved Mail/Receipt.msg"]);
+        assert_eq!(
+            command_line("OUTLOOK.EXE", &args),
+            "OUTLOOK.EXE /f \"C:/Saved Mail/Receipt.msg\""
+        );
+    }
+
+    #[test]
+    fn with_profile() {
+        let mb = MessageBuilder::new().with_subject("Hi");
+        assert_eq!(mb.build_command(), ["/c", "ipm.note", "/m", "?subject=Hi"]);
+        let mb = mb.with_profile("Work Account");
+        assert_eq!(
+            mb.build_command(),
+            [
+                "/profile",
+                "Work Account",
+                "/c",
+                "ipm.note",
+                "/m",
+                "?subject=Hi"
+            ]
+        );
+    }
+
+    #[test]
+    fn recycle() {
+        let mb = MessageBuilder::new().with_subject("Hi");
+        assert!(!mb.build_command().contains(&"/recycle".to_owned()));
+        let mb = mb.recycle(true);
+        assert_eq!(mb.build_command().last().unwrap(), "/recycle");
+        assert!(!mb
+            .recycle(false)
+            .build_command()
+            .contains(&"/recycle".to_owned()));
+    }
+
+    #[test]
+    fn attachment_with_spaces() {
+        let mb = MessageBuilder::new().with_attachment("C:/My Documents/100% done.txt");
+        let args = mb.build_command();
+        assert_eq!(args[4..], ["/a", "C:/My Documents/100% done.txt"]);
+        assert_eq!(
+            command_line("OUTLOOK.EXE", &args[4..]),
+            "OUTLOOK.EXE /a \"C:/My Documents/100% done.txt\""
+        );
+    }
+
+    #[test]
+    fn with_importance() {
+        let mb = MessageBuilder::new();
+        assert_eq!(mb.importance(), Importance::Normal);
+        let mb = mb.with_importance(Importance::High);
+        assert_eq!(mb.importance(), Importance::High);
+        assert_eq!(mb.build_command(), MessageBuilder::new().build_command());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            MessageBuilder::new().to_string(),
+            "To: (no recipients); Subject: (no subject)"
+        );
+        let mb = MessageBuilder::new()
+            .with_recipient("a@b.com")
+            .with_subject("Hello")
+            .with_attachment("C:/tmp/file.txt");
+        assert_eq!(mb.to_string(), "To: a@b.com; Subject: Hello (1 attachment)");
+        let mb = mb
+            .with_recipient("c@d.com")
+            .with_recipient_cc("e@f.com")
+            .with_attachment("C:/tmp/other.txt");
+        assert_eq!(
+            mb.to_string(),
+            "To: a@b.com, c@d.com; CC: e@f.com; Subject: Hello (2 attachments)"
+        );
+    }
+
+    #[test]
+    fn clear() {
+        let base = MessageBuilder::new()
+            .with_subject("Status")
+            .with_recipient("a@example.org")
+            .with_recipient_cc("b@example.org")
+            .with_recipient_bcc("c@example.org")
+            .with_attachment("C:/tmp/file.txt");
+        let mb = base.clone().clear_recipients();
+        assert!(mb.to.is_empty());
+        assert_eq!(mb.cc, base.cc);
+        let mb = mb.clear_cc().clear_bcc().clear_attachments();
+        assert!(mb.cc.is_empty());
+        assert!(mb.bcc.is_empty());
+        assert!(mb.files.is_empty());
+        assert_eq!(mb.subj, "Status");
+    }
+
+    #[test]
+    fn append_body() {
+        let mb = MessageBuilder::new()
+            .append_body("Hi Jane,")
+            .append_body("The report is attached.")
+            .append_body("Thanks,\nJohn");
+        assert_eq!(mb.body, "Hi Jane,\nThe report is attached.\nThanks,\nJohn");
+    }
+
+    #[test]
+    fn with_signature() {
+        let mb = MessageBuilder::new().with_signature("-- \nJohn & Co");
+        assert_eq!(mb.build_command()[3], "?body=-- %0D%0AJohn %26 Co");
+        let mb = mb.with_body("Hello");
+        assert_eq!(mb.body, "Hello");
+        assert_eq!(
+            mb.build_command()[3],
+            "?body=Hello%0D%0A%0D%0A-- %0D%0AJohn %26 Co"
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawn_async() {
+        let _: fn(MessageBuilder) -> io::Result<tokio::process::Child> =
+            MessageBuilder::spawn_async;
+    }
+
+    #[test]
+    fn outlook_path() {
+        assert_eq!(super::outlook_path(), OUTLOOK_EXE.as_deref());
+        assert_eq!(super::outlook_path(), locate_outlook_exe().as_deref());
+        assert_eq!(outlook_exe().ok(), super::outlook_path());
+    }
+
+    #[test]
+    fn is_available() {
+        assert_eq!(super::is_available(), super::outlook_path().is_some());
+    }
+
+    #[test]
+    fn percent_escape_plus() {
+        let mb = MessageBuilder::new().with_subject("C++ code review");
+        assert_eq!(mb.build_command()[3], "?subject=C%2B%2B code review");
+    }
+
+    #[test]
+    fn with_body_html() {
+        let mb = MessageBuilder::new().with_body_html("<p>Fish &amp; chips</p>");
+        assert!(mb.is_html());
+        assert_eq!(mb.body(), "<p>Fish &amp; chips</p>");
+        assert_eq!(
+            mb.build_command()[3],
+            "?body=%3Cp%3EFish %26amp; chips%3C/p%3E"
+        );
+        assert!(!MessageBuilder::new().with_body("Hi").is_html());
+    }
+
+    #[test]
+    fn with_item_class() {
+        let mb = MessageBuilder::new();
+        assert_eq!(mb.build_command()[..2], ["/c", "ipm.note"]);
+        let mb = mb.with_item_class("ipm.post");
+        assert_eq!(mb.build_command()[..2], ["/c", "ipm.post"]);
+    }
+
+    #[test]
+    fn try_spawn() {
+        let err = MessageBuilder::new()
+            .with_executable("C:/does/not/exist/OUTLOOK.EXE")
+            .try_spawn()
+            .unwrap_err();
+        assert!(matches!(err, SpawnError::Spawn(_)));
+        let err = io::Error::from(SpawnError::OutlookNotFound);
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let mut child = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .try_spawn()
+            .unwrap();
+        assert!(child.wait().is_ok());
+    }
+
+    #[test]
+    fn command_too_long() {
+        let mb = MessageBuilder::new()
+            .with_body("a".repeat(MAX_COMMAND_LEN))
+            .with_executable(STUB_EXE);
+        match mb.clone().try_spawn() {
+            Err(SpawnError::CommandTooLong { len }) => assert!(len > MAX_COMMAND_LEN),
+            _ => panic!("expected SpawnError::CommandTooLong"),
+        }
+        assert_eq!(mb.spawn().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn save_draft() {
+        let mut child = MessageBuilder::new()
+            .with_subject("Draft")
+            .with_executable(STUB_EXE)
+            .save_draft()
+            .unwrap();
+        assert!(child.wait().is_ok());
+    }
+
+    #[test]
+    fn no_preview() {
+        let mb = MessageBuilder::new();
+        assert!(!mb.build_command().contains(&"/nopreview".to_owned()));
+        let mb = mb.no_preview(true);
+        assert_eq!(mb.build_command().last().unwrap(), "/nopreview");
+    }
+
+    #[test]
+    fn with_body_from_file() {
+        let path = std::env::temp_dir().join("outlook-exe-with-body-from-file.txt");
+        fs::write(&path, "Line with spaces\nAnother line").unwrap();
+        let mb = MessageBuilder::new().with_body_from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mb.unwrap().body, "Line with spaces\nAnother line");
+        assert!(MessageBuilder::new().with_body_from_file(&path).is_err());
+    }
+
+    #[test]
+    fn dedup_recipients() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.org")
+            .with_recipient("b@example.org")
+            .with_recipient("A@Example.org")
+            .with_recipient_cc("c@example.org")
+            .with_recipient_cc("Bee <B@example.org>")
+            .with_recipient_bcc("c@example.org")
+            .with_recipient_bcc("d@example.org")
+            .dedup_recipients();
+        assert_eq!(mb.to, ["a@example.org", "b@example.org"]);
+        assert_eq!(mb.cc, ["c@example.org"]);
+        assert_eq!(mb.bcc, ["d@example.org"]);
+    }
+
+    #[test]
+    fn select_folder() {
+        assert_eq!(select_args("calendar"), ["/select", "outlook:calendar"]);
+        assert_eq!(select_args("inbox"), ["/select", "outlook:inbox"]);
+        assert_eq!(select_args("contacts"), ["/select", "outlook:contacts"]);
+    }
+
+    #[test]
+    fn with_attachment_path() {
+        let mb = MessageBuilder::new().with_attachment_path(PathBuf::from("C:/tmp/file.txt"));
+        if cfg!(windows) {
+            assert_eq!(mb.files, ["C:\\tmp\\file.txt"]);
+        } else {
+            assert_eq!(mb.files, ["C:/tmp/file.txt"]);
+        }
+    }
+
+    #[test]
+    fn with_recipient_separator() {
+        let mb = MessageBuilder::new()
+            .with_recipients(["a@example.org", "b@example.org"].iter().copied())
+            .with_recipients_cc(["c@example.org", "d@example.org"].iter().copied());
+        assert_eq!(
+            mb.build_command()[3],
+            "a@example.org;b@example.org?cc=c@example.org;d@example.org"
+        );
+        let mb = mb.with_recipient_separator(',');
+        assert_eq!(
+            mb.build_command()[3],
+            "a@example.org,b@example.org?cc=c@example.org,d@example.org"
+        );
+    }
+
+    #[test]
+    fn spawn_logged() {
+        let mb = MessageBuilder::new()
+            .with_subject("Audit me")
+            .with_executable(STUB_EXE);
+        let expected = mb.dry_run().unwrap();
+        let (mut child, cmd) = mb.spawn_logged().unwrap();
+        assert!(child.wait().is_ok());
+        assert_eq!(cmd, expected);
+        assert_eq!(
+            cmd,
+            format!("{} /c ipm.note /m \"?subject=Audit me\"", STUB_EXE)
+        );
+    }
+
+    #[test]
+    fn message_builder_mut() {
+        let mut mb = MessageBuilder::new();
+        for addr in &["a@example.org", "b@example.org"] {
+            mb.push_recipient(*addr);
+        }
+        mb.push_recipient_cc("c@example.org")
+            .push_recipient_bcc("d@example.org")
+            .set_subject("Hello, World!")
+            .set_body("Line with spaces\nAnother line")
+            .push_attachment("C:/tmp/file.txt");
+        assert_eq!(
+            mb,
+            MessageBuilder::new()
+                .with_recipient("a@example.org")
+                .with_recipient("b@example.org")
+                .with_recipient_cc("c@example.org")
+                .with_recipient_bcc("d@example.org")
+                .with_subject("Hello, World!")
+                .with_body("Line with spaces\nAnother line")
+                .with_attachment("C:/tmp/file.txt")
+        );
+    }
+
+    #[test]
+    fn from_iter() {
+        let addrs = vec![
+            "a@example.org".to_owned(),
+            "b@example.org".to_owned(),
+            "c@example.org".to_owned(),
+        ];
+        let mut mb: MessageBuilder = addrs.into_iter().collect();
+        assert_eq!(mb.to.len(), 3);
+        mb.extend(Some("d@example.org"));
+        assert_eq!(mb.to.len(), 4);
+        assert_eq!(mb.to[3], "d@example.org");
+    }
+
+    #[test]
+    fn recipient_with_separator() {
+        let mb = MessageBuilder::new()
+            .with_named_recipient("Smith; Jones Ltd", "x@example.org")
+            .with_recipient("y@example.org");
+        let to = &mb.build_command()[3];
+        assert_eq!(to.split(';').count(), 2);
+        assert_eq!(
+            to,
+            "%22Smith%3B Jones Ltd%22 %3Cx@example.org%3E;y@example.org"
+        );
+        let mb = mb.with_recipient_separator(',');
+        assert_eq!(mb.build_command()[3].split(',').count(), 2);
+    }
+
+    #[test]
+    fn background() {
+        let mb = MessageBuilder::new().with_executable(STUB_EXE);
+        assert!(!mb.background);
+        let mb = mb.background(true);
+        assert!(mb.background);
+        assert_eq!(mb.build_command(), MessageBuilder::new().build_command());
+        assert!(mb.spawn_and_wait().is_ok());
+    }
+
+    #[test]
+    fn with_from() {
+        let mb = MessageBuilder::new().with_from("support@example.com");
+        assert_eq!(mb.from(), "support@example.com");
+        assert_eq!(mb.build_command(), MessageBuilder::new().build_command());
+    }
+
+    #[test]
+    fn const_builder() {
+        const MB: MessageBuilder = MessageBuilder::new()
+            .with_importance(Importance::High)
+            .with_recipient_separator(',')
+            .recycle(true)
+            .no_preview(true)
+            .background(true);
+        assert_eq!(MB.importance(), Importance::High);
+        assert_eq!(MB.build_command()[4..], ["/recycle", "/nopreview"]);
+    }
+
+    #[test]
+    fn strip_bom() {
+        let mb = MessageBuilder::new().with_body("\u{feff}Hello,\n\u{feff}World");
+        assert_eq!(mb.body(), "Hello,\n\u{feff}World");
+        let mb = MessageBuilder::new().with_body_html("\u{feff}<p>Hi</p>");
+        assert_eq!(mb.body(), "<p>Hi</p>");
+
+        let path = std::env::temp_dir().join("outlook-exe-strip-bom.txt");
+        fs::write(&path, "\u{feff}From Notepad").unwrap();
+        let mb = MessageBuilder::new().with_body_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mb.body(), "From Notepad");
+    }
+
+    #[test]
+    fn with_recipients_str() {
+        let mb = MessageBuilder::new()
+            .with_recipients_str(" a@example.com,b@example.com ;; c@example.com; ,");
+        assert_eq!(
+            mb.recipients(),
+            ["a@example.com", "b@example.com", "c@example.com"]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let base = MessageBuilder::new()
+            .with_subject("Weekly report")
+            .with_recipient_cc("team@example.com")
+            .with_importance(Importance::High);
+        let mb = base.clone().merge(
+            MessageBuilder::new()
+                .with_recipient("boss@example.com")
+                .with_recipient_cc("me@example.com"),
+        );
+        assert_eq!(mb.subject(), "Weekly report");
+        assert_eq!(mb.recipients(), ["boss@example.com"]);
+        assert_eq!(mb.cc(), ["team@example.com", "me@example.com"]);
+        assert_eq!(mb.importance(), Importance::High);
+
+        let mb = base.merge(MessageBuilder::new().with_subject("Monthly report"));
+        assert_eq!(mb.subject(), "Monthly report");
+    }
+
+    #[test]
+    fn with_subject_truncated() {
+        let mb = MessageBuilder::new().with_subject_truncated("Grüße aus Köln", 8);
+        assert_eq!(mb.subject(), "Grüße a…");
+        let mb = MessageBuilder::new().with_subject_truncated("Grüße", 5);
+        assert_eq!(mb.subject(), "Grüße");
+        let mb = MessageBuilder::new().with_subject_truncated("Grüße", 0);
+        assert_eq!(mb.subject(), "");
+    }
+
+    #[test]
+    fn spawn_pid() {
+        let pid = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .spawn_pid()
+            .unwrap();
+        assert_ne!(pid, 0);
+    }
+
+    #[test]
+    fn with_followup_flag() {
+        assert!(!MessageBuilder::new().followup_flag());
+        let mb = MessageBuilder::new().with_followup_flag(true);
+        assert!(mb.followup_flag());
+        assert_eq!(mb.build_command(), MessageBuilder::new().build_command());
+    }
+
+    #[test]
+    fn with_body_rtf() {
+        let mb = MessageBuilder::new().with_body_rtf(r"{\rtf1 Hi}");
+        assert_eq!(mb.body_format(), BodyFormat::Rtf);
+        assert!(!mb.is_html());
+        assert_eq!(mb.body(), r"{\rtf1 Hi}");
+        assert_eq!(
+            rtf_escape("a\\b {c}\r\nd\te 😀"),
+            "a\\\\b \\{c\\}\\par\nd\\tab e \\u-10179?\\u-8704?"
+        );
+    }
+
+    #[test]
+    fn spawn_with_retry() {
+        let mut child = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .spawn_with_retry(3)
+            .unwrap();
+        assert!(child.wait().is_ok());
+
+        let start = std::time::Instant::now();
+        let err = MessageBuilder::new()
+            .with_executable("C:/does/not/exist/OUTLOOK.EXE")
+            .spawn_with_retry(3)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+
+        let err = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_body("x".repeat(MAX_COMMAND_LEN))
+            .spawn_with_retry(3)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut recycled = Vec::new();
+        let mut backoff = Vec::new();
+        let result = MessageBuilder::new().retry(
+            4,
+            |mb| {
+                recycled.push(mb.recycle);
+                if recycled.len() < 3 {
+                    Err(SpawnError::Spawn(io::ErrorKind::Other.into()))
+                } else {
+                    Ok(recycled.len())
+                }
+            },
+            |delay| backoff.push(delay),
+        );
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(recycled, [false, true, true]);
+        assert_eq!(
+            backoff,
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+
+        let mut spawns = 0;
+        let err = MessageBuilder::new()
+            .retry(
+                2,
+                |_| -> Result<(), _> {
+                    spawns += 1;
+                    Err(SpawnError::Spawn(io::ErrorKind::Other.into()))
+                },
+                |_| {},
+            )
+            .unwrap_err();
+        assert!(matches!(err, SpawnError::Spawn(_)));
+        assert_eq!(spawns, 2);
+    }
+
+    #[test]
+    fn with_raw_arg() {
+        let args = MessageBuilder::new()
+            .with_raw_arg("/safe")
+            .with_raw_args(["/select", "outlook:inbox"].iter().copied())
+            .recycle(true)
+            .build_command();
+        assert_eq!(args[4..], ["/recycle", "/safe", "/select", "outlook:inbox"]);
+    }
+
+    #[test]
+    fn sort_recipients() {
+        let mb = MessageBuilder::new()
+            .with_recipients(
+                ["carol@example.com", "Bob@example.com", "alice@example.com"]
+                    .iter()
+                    .copied(),
+            )
+            .with_recipients_cc(["z@example.com", "Y@example.com"].iter().copied())
+            .sort_recipients();
+        assert_eq!(
+            mb.recipients(),
+            ["alice@example.com", "Bob@example.com", "carol@example.com"]
+        );
+        assert_eq!(mb.cc(), ["Y@example.com", "z@example.com"]);
+    }
+
+    #[test]
+    fn with_body_template() {
+        let mut vars = HashMap::new();
+        vars.insert("name".to_owned(), "Ann".to_owned());
+        vars.insert("total".to_owned(), "$5 & tax".to_owned());
+        let mb = MessageBuilder::new()
+            .with_body_template("Hi {name}, you owe {total}. {unknown} {name", &vars);
+        assert_eq!(mb.body(), "Hi Ann, you owe $5 & tax. {unknown} {name");
+        assert_eq!(
+            mb.build_command()[3],
+            "?body=Hi Ann, you owe $5 %26 tax. {unknown} {name"
+        );
+    }
+
+    #[test]
+    fn with_current_dir() {
+        let mb = MessageBuilder::new().with_executable(STUB_EXE);
+        assert!(mb
+            .clone()
+            .with_current_dir(std::env::temp_dir())
+            .spawn_and_wait()
+            .is_ok());
+        assert!(mb
+            .with_current_dir("C:/does/not/exist")
+            .spawn_and_wait()
+            .is_err());
+    }
+
+    #[test]
+    fn with_env() {
+        let mb = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_env("DROPPED", "1")
+            .env_clear()
+            .with_env("OUTLOOK_EXE_TEST", "1");
+        let (cmd, _) = mb.command().unwrap();
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            [(OsStr::new("OUTLOOK_EXE_TEST"), Some(OsStr::new("1")))]
+        );
+    }
+
+    #[test]
+    fn build() {
+        let errors = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_recipient_cc("team@example.com")
+            .with_recipient_bcc("nobody")
+            .with_recipient_bcc("@example.com")
+            .with_body("x".repeat(MAX_COMMAND_LEN))
+            .build()
+            .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[..2],
+            [
+                BuilderError::Validation(ValidationError::InvalidAddress {
+                    field: RecipientField::Bcc,
+                    recipient: "nobody".to_owned(),
+                }),
+                BuilderError::Validation(ValidationError::InvalidAddress {
+                    field: RecipientField::Bcc,
+                    recipient: "@example.com".to_owned(),
+                }),
+            ]
+        );
+        assert!(matches!(
+            errors[2],
+            BuilderError::Spawn(SpawnError::CommandTooLong { .. })
+        ));
+        assert_eq!(errors[2].clone(), errors[2]);
+
+        let errors = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_subject("Hello")
+            .with_static_subject("Hello again")
+            .with_body("World")
+            .with_body_html("<p>World</p>")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                BuilderError::SubjectAlreadySet,
+                BuilderError::BodyAlreadySet,
+                BuilderError::Validation(ValidationError::NoRecipients),
+            ]
+        );
+
+        let ready = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_recipient("someone@example.com")
+            .build()
+            .unwrap();
+        assert_eq!(ready.message().recipients(), ["someone@example.com"]);
+        assert!(ready.spawn().unwrap().wait().is_ok());
+    }
+
+    #[test]
+    fn with_attachment_unc() {
+        let unc = r"\\server\share\100% done #1.txt";
+        let mb = MessageBuilder::new().with_attachment(unc);
+        let args = mb.build_command();
+        assert_eq!(args[4..], ["/a", unc]);
+        assert_eq!(
+            command_line("OUTLOOK.EXE", &args[4..]),
+            r#"OUTLOOK.EXE /a "\\server\share\100% done #1.txt""#
+        );
+        if cfg!(windows) {
+            let mb = MessageBuilder::new()
+                .with_attachment_path(r"\\?\UNC\server\share\a.txt")
+                .with_attachment_path(r"\\?\C:\Temp\b.txt")
+                .with_attachment_path("//server/share/c.txt");
+            assert_eq!(
+                mb.attachments(),
+                [
+                    r"\\server\share\a.txt",
+                    r"C:\Temp\b.txt",
+                    r"\\server\share\c.txt"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn with_priority() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.com")
+            .with_subject("Outage");
+        assert_eq!(mb.to_mailto(), "mailto:a@example.com?subject=Outage");
+        let mb = mb.with_priority(Importance::High);
+        assert_eq!(mb.importance(), Importance::High);
+        assert_eq!(
+            mb.to_mailto(),
+            "mailto:a@example.com?subject=Outage&X-Priority=1"
+        );
+        assert_eq!(MessageBuilder::from_mailto(&mb.to_mailto()), Ok(mb));
+        let mb = MessageBuilder::from_mailto("mailto:?X-Priority=5%20(Lowest)").unwrap();
+        assert_eq!(mb.importance(), Importance::Low);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn not_windows() {
+        assert_eq!(super::outlook_path(), None);
+        assert_eq!(super::default_profile(), None);
+        assert!(super::list_profiles().is_empty());
+        let err = MessageBuilder::new()
+            .with_recipient("a@example.com")
+            .try_spawn()
+            .unwrap_err();
+        assert!(matches!(err, SpawnError::OutlookNotFound));
+    }
+
+    #[test]
+    fn rendered_body() {
+        let mb = MessageBuilder::new()
+            .with_body("Q&A\r\nat 5?\n")
+            .with_signature("-- Ann");
+        assert_eq!(
+            mb.rendered_body(),
+            "Q%26A%0D%0Aat 5%3F%0D%0A%0D%0A%0D%0A-- Ann"
+        );
+        assert_eq!(
+            mb.build_command()[3],
+            format!("?body={}", mb.rendered_body())
+        );
+        assert_eq!(MessageBuilder::new().rendered_body(), "");
+    }
+
+    #[test]
+    fn spawn_safe_mode() {
+        assert_eq!(safe_mode_args(), ["/safe"]);
+    }
+
+    #[test]
+    fn appointment_attendees() {
+        let ab = AppointmentBuilder::new()
+            .with_attendee("a@example.com")
+            .with_attendee("\"Doe; Jane\" <jane@example.com>")
+            .with_optional_attendee("b@example.com")
+            .with_subject("Sync");
+        assert_eq!(
+            ab.build_command(),
+            [
+                "/c",
+                "ipm.appointment",
+                "/m",
+                "a@example.com;%22Doe%3B Jane%22 %3Cjane@example.com%3E?cc=b@example.com&subject=Sync"
+            ]
+        );
+        assert_eq!(
+            AppointmentBuilder::new()
+                .with_optional_attendee("b@example.com")
+                .build_command()[3],
+            "?cc=b@example.com"
+        );
+        let ab = AppointmentBuilder::new()
+            .with_attendee("a@example.com")
+            .with_attendee("\"Doe, Jane\" <jane@example.com>")
+            .with_optional_attendee("b@example.com")
+            .with_optional_attendee("c@example.com")
+            .with_subject("Sync!")
+            .with_recipient_separator(',')
+            .with_escape_set(&['!']);
+        assert_eq!(ab.escape_set(), ['!']);
+        assert_eq!(
+            ab.build_command()[3],
+            "a@example.com,\"Doe%2C Jane\" <jane@example.com>\
+             ?cc=b@example.com,c@example.com&subject=Sync%21"
+        );
+    }
+
+    #[test]
+    fn preview_mailto() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.com")
+            .with_subject("Q&A");
+        assert_eq!(mb.preview_mailto(), "mailto:a@example.com?subject=Q%26A");
+        assert_eq!(
+            mb.preview_mailto(),
+            format!("mailto:{}", mb.build_command()[3])
+        );
+    }
+
+    #[test]
+    fn spawn_strict() {
+        let err = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_subject("Hi")
+            .spawn_strict()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref(),
+            Some(&ValidationError::NoRecipients)
+        );
+        let mut child = MessageBuilder::new()
+            .with_executable(STUB_EXE)
+            .with_recipient_bcc("a@example.com")
+            .spawn_strict()
+            .unwrap();
+        assert!(child.wait().is_ok());
+    }
+
+    #[test]
+    fn with_reminder() {
+        let ab = AppointmentBuilder::new().with_subject("Sync");
+        assert_eq!(ab.reminder(), None);
+        let args = ab.build_command();
+        let ab = ab.with_reminder(Duration::from_secs(15 * 60));
+        assert_eq!(ab.reminder(), Some(Duration::from_secs(15 * 60)));
+        assert_eq!(ab.build_command(), args);
+
+        let tb = TaskBuilder::new().with_reminder(Duration::from_secs(24 * 60 * 60));
+        assert_eq!(tb.reminder(), Some(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(tb.build_command(), TaskBuilder::new().build_command());
+    }
+
+    #[test]
+    fn cc_self() {
+        let mb = MessageBuilder::new().with_recipient_cc("team@example.com");
+        let resolved = mb.clone().cc_resolved(|| Some("me@example.com".to_owned()));
+        assert_eq!(resolved.cc(), ["team@example.com", "me@example.com"]);
+        assert_eq!(mb.clone().cc_resolved(|| None), mb);
+        assert_eq!(
+            domain_address(Some("JDoe".to_owned()), Some("CORP.EXAMPLE.COM".to_owned())),
+            Some("jdoe@corp.example.com".to_owned())
+        );
+        assert_eq!(domain_address(Some("jdoe".to_owned()), None), None);
+    }
+
+    #[test]
+    fn with_encoded() {
+        let mb = MessageBuilder::new()
+            .with_subject_encoded("Hello%20World")
+            .with_body_encoded("100% done%0D%0ACaf%C3%A9 %zz");
+        assert_eq!(mb.subject(), "Hello World");
+        assert_eq!(mb.body(), "100% done\r\nCafé %zz");
+        assert_eq!(
+            mb.build_command()[3],
+            "?subject=Hello World&body=100%25 done%0D%0ACafé %25zz"
+        );
+        let mb = MessageBuilder::new().with_subject_encoded("%FF%20");
+        assert_eq!(mb.subject(), "%FF%20");
+    }
+
+    #[test]
+    fn validate_strict() {
+        let mb = MessageBuilder::new().with_recipient("a@example.com");
+        assert_eq!(mb.validate_strict(), Ok(()));
+        let missing = "C:/does/not/exist/report.pdf";
+        assert_eq!(
+            mb.clone().with_attachment(missing).validate_strict(),
+            Err(ValidationError::AttachmentMissing(missing.to_owned()))
+        );
+
+        let path = std::env::temp_dir().join("outlook-exe-validate-strict.txt");
+        fs::write(&path, "attached").unwrap();
+        let ok = mb
+            .clone()
+            .with_attachment_path(&path)
+            .validate_strict()
+            .and(
+                mb.with_current_dir(std::env::temp_dir())
+                    .with_attachment("outlook-exe-validate-strict.txt")
+                    .validate_strict(),
+            );
+        fs::remove_file(&path).unwrap();
+        assert_eq!(ok, Ok(()));
+    }
+
+    #[test]
+    fn with_static() {
+        let mb = MessageBuilder::new()
+            .with_static_subject("Status")
+            .with_static_body("\u{feff}All good & green");
+        assert!(matches!(mb.subj, Cow::Borrowed(_)));
+        assert!(matches!(mb.body, Cow::Borrowed(_)));
+        assert_eq!(mb.body(), "All good & green");
+        let owned = MessageBuilder::new()
+            .with_subject("Status")
+            .with_body("All good & green");
+        assert_eq!(mb, owned);
+        assert_eq!(mb.build_command(), owned.build_command());
+        assert_eq!(mb.append_body("Thanks").body(), "All good & green\nThanks");
+    }
+
+    #[test]
+    fn to_powershell() {
+        let mb = MessageBuilder::new()
+            .with_executable("C:\\Program Files\\OUTLOOK.EXE")
+            .with_subject("It's \"$5\" `now`")
+            .with_attachment("C:\\Bob’s Files\\a.txt");
+        assert_eq!(
+            mb.to_powershell().unwrap(),
+            "& 'C:\\Program Files\\OUTLOOK.EXE' @('/c', 'ipm.note', '/m', \
+             '?subject=It''s %22$5%22 `now`', '/a', 'C:\\Bob’’s Files\\a.txt')"
+        );
+    }
+
+    #[test]
+    fn to_cmd() {
+        let mb = MessageBuilder::new()
+            .with_executable("OUTLOOK.EXE")
+            .with_subject("R&D")
+            .with_body("100% done")
+            .with_raw_arg("a&b^c");
+        assert_eq!(
+            mb.to_cmd().unwrap(),
+            "OUTLOOK.EXE /c ipm.note /m \"?subject=R%%26D&body=100%%25 done\" a^&b^^c"
+        );
+        // CMD ends the quoted region at the escaped quote.
+        assert_eq!(
+            cmd_escape(&super::quote_arg("a\"b & <c>")),
+            r#""a\"b ^& ^<c^>""#
+        );
+    }
+
+    #[test]
+    fn embedding() {
+        let mb = MessageBuilder::new().no_preview(true);
+        assert!(!mb.build_command().contains(&"/embedding".to_owned()));
+        let args = mb.embedding(true).with_raw_arg("/safe").build_command();
+        assert_eq!(args[4..], ["/nopreview", "/embedding", "/safe"]);
+    }
+
+    #[test]
+    fn outlook_version() {
+        assert_eq!(
+            cur_ver_version("Outlook.Application.16").as_deref(),
+            Some("16")
+        );
+        assert_eq!(cur_ver_version("Outlook.Application"), None);
+        assert_eq!(cur_ver_version("Outlook.Application.x"), None);
+        if !cfg!(windows) {
+            assert_eq!(super::outlook_version(), None);
+        }
+    }
+
+    #[test]
+    fn with_body_wrapped() {
+        let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+                         eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim \
+                         ad minim veniam, quis nostrud exercitation ullamco laboris.";
+        let url = "https://example.com/".to_owned() + &"a".repeat(80);
+        let body = format!("Hi,\n\n  {}\n{}\n-- Ann", paragraph, url);
+        let mb = MessageBuilder::new().with_body_wrapped(&body, 72);
+        let lines: Vec<_> = mb.body().split('\n').collect();
+        assert_eq!(lines[..2], ["Hi,", ""]);
+        assert!(lines[2].starts_with("  Lorem ipsum"));
+        assert!(lines[2..lines.len() - 2]
+            .iter()
+            .all(|l| l.chars().count() <= 72));
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[lines.len() - 2..], [url.as_str(), "-- Ann"]);
+        assert_eq!(
+            lines[2..5].join(" ").trim_start(),
+            paragraph.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+
+        let mb = MessageBuilder::new().with_body_wrapped("a\r\nb", 72);
+        assert_eq!(mb.body(), "a\r\nb");
+        let mb = MessageBuilder::new().with_body_wrapped("a b c\r\nd", 3);
+        assert_eq!(mb.body(), "a b\r\nc\r\nd");
+        let mb = MessageBuilder::new().with_body_wrapped("    indented code line that is long", 10);
+        assert_eq!(mb.body(), "    indented\ncode line\nthat is\nlong");
+        let mb = MessageBuilder::new().with_body_wrapped("  a b\r\n", 3);
+        assert_eq!(mb.body(), "  a\r\nb\r\n");
+    }
+
+    #[test]
+    fn all_recipients() {
+        let mb = MessageBuilder::new()
+            .with_recipients(["a@example.com", "b@example.com"].iter().copied())
+            .with_recipient_cc("c@example.com")
+            .with_recipient_bcc("d@example.com");
+        assert_eq!(mb.all_recipients().count(), 4);
+        assert_eq!(mb.all_recipients().last(), Some("d@example.com"));
+        assert_eq!(MessageBuilder::new().all_recipients().count(), 0);
+    }
+
+    #[test]
+    fn with_records_copy() {
+        let mb = MessageBuilder::new()
+            .with_recipient_bcc("a@example.com")
+            .with_records_copy("records@example.com");
+        assert_eq!(mb.bcc(), ["a@example.com", "records@example.com"]);
+        assert!(mb.recipients().is_empty());
+    }
+
+    #[test]
+    fn attachment_size() {
+        let path = std::env::temp_dir().join("outlook-exe-attachment-size.txt");
+        fs::write(&path, [0; 1000]).unwrap();
+        let mb = MessageBuilder::new()
+            .with_attachment_path(&path)
+            .with_attachment_path(&path);
+        let size = mb.attachment_size();
+        let checked = mb.check_attachment_size(MAX_ATTACHMENT_SIZE);
+        let exceeded = mb.check_attachment_size(1999);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(size.unwrap(), 2000);
+        assert_eq!(checked.unwrap(), 2000);
+        assert_eq!(exceeded.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let err = MessageBuilder::new()
+            .with_attachment("C:/does/not/exist/report.pdf")
+            .attachment_size()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(MessageBuilder::new().attachment_size().unwrap(), 0);
+    }
+
+    #[test]
+    fn spawn_guarded() {
+        let mb = MessageBuilder::new().with_executable(STUB_EXE);
+        let guard = mb.clone().spawn_guarded().unwrap();
+        assert_ne!(guard.id(), 0);
+        drop(guard);
+        assert!(mb.spawn_guarded().unwrap().wait().is_ok());
+    }
+
+    #[test]
+    fn with_subject_prefix() {
+        let mb = MessageBuilder::new()
+            .with_subject_prefix("[PROJ-123] ")
+            .with_subject("Build broken");
+        assert_eq!(mb.subject(), "Build broken");
+        assert_eq!(mb.build_command()[3], "?subject=[PROJ-123] Build broken");
+        assert_eq!(
+            mb.to_mailto(),
+            "mailto:?subject=%5BPROJ-123%5D%20Build%20broken"
+        );
+        let mb = MessageBuilder::new().with_subject_prefix("[PROJ-123]");
+        assert_eq!(mb.build_command()[3], "?subject=[PROJ-123]");
+    }
+
+    #[test]
+    fn with_account() {
+        let mb = MessageBuilder::new().with_account("Support Team");
+        assert_eq!(mb.account(), "Support Team");
+        assert_eq!(mb.build_command(), MessageBuilder::new().build_command());
+    }
+
+    #[test]
+    fn recipient() {
+        let jane = Recipient::named("Doe, Jane", "jane@example.com");
+        assert_eq!(jane.name(), Some("Doe, Jane"));
+        assert_eq!(jane.email(), "jane@example.com");
+        assert_eq!(jane.to_string(), "\"Doe, Jane\" <jane@example.com>");
+        let mb = MessageBuilder::new()
+            .with_recipient(jane)
+            .with_recipient("bob@example.com")
+            .with_recipient_cc(Recipient::new("carol@example.com"))
+            .with_recipient_bcc(String::from("dave@example.com"));
+        assert_eq!(
+            mb.recipients(),
+            ["\"Doe, Jane\" <jane@example.com>", "bob@example.com"]
+        );
+        assert_eq!(mb.cc(), ["carol@example.com"]);
+        assert_eq!(mb.bcc(), ["dave@example.com"]);
+        assert_eq!(
+            mb.build_command()[3],
+            "%22Doe%2C Jane%22 %3Cjane@example.com%3E;bob@example.com\
+             ?cc=carol@example.com&bcc=dave@example.com"
+        );
+        let mb = MessageBuilder::new()
+            .with_recipient(Cow::Borrowed("erin@example.com"))
+            .with_recipient_cc(Box::<str>::from("frank@example.com"));
+        assert_eq!(mb.recipients(), ["erin@example.com"]);
+        assert_eq!(mb.cc(), ["frank@example.com"]);
+    }
+
+    #[cfg(all(feature = "log", not(windows)))]
+    #[test]
+    fn log_outlook_not_found() {
+        use std::sync::Mutex;
+
+        struct TestLogger(Mutex<Vec<String>>);
+
+        impl log::Log for TestLogger {
+            fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+                metadata.level() <= log::Level::Warn
+            }
+
+            fn log(&self, record: &log::Record<'_>) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        lazy_static! {
+            static ref LOGGER: TestLogger = TestLogger(Mutex::new(Vec::new()));
+        }
+
+        log::set_logger(&*LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+        assert!(MessageBuilder::new().spawn().is_err());
+        assert!(LOGGER
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|msg| msg.starts_with("OUTLOOK.EXE could not be located")));
+    }
+
+    #[test]
+    fn with_escape_set() {
+        let mb = MessageBuilder::new()
+            .with_subject("100% #1 & !")
+            .with_body("a!b");
+        assert_eq!(mb.escape_set(), ESCAPE_SET);
+        assert_eq!(mb.build_command()[3], "?subject=100%25 %231 %26 !&body=a!b");
+        let set = [ESCAPE_SET, &['!']].concat();
+        let mb = mb.with_escape_set(&set);
+        assert_eq!(mb.escape_set(), set.as_slice());
+        assert_eq!(
+            mb.build_command()[3],
+            "?subject=100%25 %231 %26 %21&body=a%21b"
+        );
+        let mb = MessageBuilder::new()
+            .with_body("café %\n")
+            .with_escape_set(&['é']);
+        assert_eq!(mb.rendered_body(), "caf%C3%A9 %25%0D%0A");
+    }
+
+    #[cfg(feature = "com")]
+    #[test]
+    fn com_properties() {
+        let mb = MessageBuilder::new()
+            .with_recipient("a@example.com")
+            .with_recipient("b@example.com")
+            .with_recipient_bcc("c@example.com")
+            .with_subject_prefix("[ci] ")
+            .with_subject("Build")
+            .with_body_html("<b>ok</b>")
+            .with_from("builds@example.com");
+        assert_eq!(
+            mb.com_properties().unwrap(),
+            [
+                ("To", "a@example.com; b@example.com".to_owned()),
+                ("BCC", "c@example.com".to_owned()),
+                ("Subject", "[ci] Build".to_owned()),
+                ("HTMLBody", "<b>ok</b>".to_owned()),
+                ("SentOnBehalfOfName", "builds@example.com".to_owned()),
+            ]
+        );
+        let mb = MessageBuilder::new().with_body_rtf("{\\rtf1 ok}");
+        assert!(matches!(
+            mb.com_properties(),
+            Err(ComError::Unsupported("RTF bodies"))
+        ));
+        #[cfg(not(windows))]
+        assert!(matches!(
+            mb.send_via_com(),
+            Err(ComError::Unsupported("platforms other than Windows"))
+        ));
+    }
+
+    #[test]
+    fn attachment_kinds() {
+        assert_eq!(file_kind("file.pdf"), Some("pdf"));
+        assert_eq!(file_kind("C:\\Reports\\Q3.final.XLSX"), Some("XLSX"));
+        assert_eq!(file_kind("C:\\v1.2\\README"), None);
+        assert_eq!(file_kind("./.gitignore"), None);
+        assert_eq!(file_kind("trailing."), None);
+        let mb = MessageBuilder::new()
+            .with_attachment("report.pdf")
+            .with_attachment("notes")
+            .with_attachment("data.csv");
+        assert_eq!(
+            mb.attachment_kinds().collect::<Vec<_>>(),
+            [Some("pdf"), None, Some("csv")]
+        );
+        assert_eq!(MessageBuilder::new().attachment_kinds().next(), None);
+        assert_eq!(mb.attachment_kind(), Some("pdf"));
+        assert_eq!(
+            MessageBuilder::new()
+                .with_attachment("file.pdf")
+                .attachment_kind(),
+            Some("pdf")
+        );
+        assert_eq!(
+            MessageBuilder::new()
+                .with_attachment("C:\\v1.2\\README")
+                .attachment_kind(),
+            None
+        );
+        assert_eq!(MessageBuilder::new().attachment_kind(), None);
     }
 }
@@ -0,0 +1,55 @@
+//! Checks the allocations made by the builder. This is its own test
+//! binary, so that its counting allocator does not affect other tests.
+
+use outlook_exe::MessageBuilder;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Counts the allocations made by each thread, so that a test can
+/// check how many it made without being disturbed by others.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the result of `f`, along with the number of allocations it
+/// made on the current thread.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[test]
+fn with_static() {
+    let (mb, allocations) = count_allocations(|| {
+        MessageBuilder::new()
+            .with_static_subject("Status")
+            .with_static_body("\u{feff}All good & green")
+    });
+    assert_eq!(allocations, 0);
+    let (owned, allocations) = count_allocations(|| {
+        MessageBuilder::new()
+            .with_subject("Status")
+            .with_body("\u{feff}All good & green")
+    });
+    assert!(allocations > 0);
+    assert_eq!(mb, owned);
+}